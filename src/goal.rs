@@ -0,0 +1,132 @@
+use array2d::Array2D;
+
+use crate::{Board, BoardError, Move};
+
+/// Common layouts to solve a Board towards, see [`Board::set_goal`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Goal {
+    /// Tiles in order row by row, with the blank in the bottom right corner
+    #[default]
+    Standard,
+    /// The blank in the top left corner, followed by the tiles in order row by row
+    BlankFirst,
+    /// Tiles in order along the rows, going back the other way on every other row, with the blank last
+    Snake,
+    /// Tiles in order clockwise around the edge and inwards from the top left corner, with the blank last
+    Spiral,
+}
+
+/// How closely a Board has to match its goal to count as solved, see [`Board::check_solved_with`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SolvedCheck {
+    /// Every tile and the blank have to be where the goal has them
+    #[default]
+    Strict,
+    /// The tiles only have to be in the same order as in the goal, wherever the blank is
+    IgnoreBlank,
+}
+
+impl Goal {
+    /// The layout of a rows x cols board in this goal
+    pub fn layout(self, rows: usize, cols: usize) -> Array2D<u32> {
+        let len = rows * cols;
+        // Row-major index of the square each tile goes on, in order starting with tile 1 and ending with the blank
+        let order: Vec<usize> = match self {
+            Goal::Standard => (0..len).collect(),
+            Goal::BlankFirst => (1..=len).map(|i| i % len).collect(),
+            Goal::Snake => (0..rows)
+                .flat_map(|row| {
+                    (0..cols).map(move |i| row * cols + if row % 2 == 0 { i } else { cols - 1 - i })
+                })
+                .collect(),
+            Goal::Spiral => {
+                let mut order = Vec::with_capacity(len);
+                let (mut top, mut left, mut bottom, mut right) = (0, 0, rows, cols);
+                while top < bottom && left < right {
+                    order.extend((left..right).map(|x| top * cols + x));
+                    order.extend((top + 1..bottom).map(|y| y * cols + right - 1));
+                    if top + 1 < bottom {
+                        order.extend((left..right - 1).rev().map(|x| (bottom - 1) * cols + x));
+                    }
+                    if left + 1 < right {
+                        order.extend((top + 1..bottom - 1).rev().map(|y| y * cols + left));
+                    }
+                    (top, left, bottom, right) = (top + 1, left + 1, bottom - 1, right - 1);
+                }
+                order
+            }
+        };
+        let mut tiles = vec![0; len];
+        for (i, square) in order.into_iter().enumerate() {
+            tiles[square] = ((i + 1) % len) as u32;
+        }
+        Array2D::from_row_major(&tiles, rows, cols).unwrap()
+    }
+}
+
+impl Board {
+    /// The layout the solvers work towards
+    pub fn goal(&self) -> &Array2D<u32> {
+        &self.goal
+    }
+
+    /// Changes the layout the solvers work towards, which has to be a valid board of the same size. Boards start
+    /// out with the [`Goal::Standard`] layout.
+    pub fn set_goal(&mut self, goal: Array2D<u32>) -> Result<(), BoardError> {
+        let (rows, cols) = (goal.num_rows(), goal.num_columns());
+        if (rows, cols) != (self.rows(), self.cols()) {
+            return Err(BoardError::GoalSize { rows, cols });
+        }
+        Board::try_new(goal.clone())?;
+        self.goal = goal;
+        Ok(())
+    }
+
+    /// Row-major index of the square each tile value belongs on in the goal
+    pub(crate) fn goal_positions(&self) -> Vec<usize> {
+        let mut positions = vec![0; self.goal.num_elements()];
+        for (i, v) in self.goal.elements_row_major_iter().enumerate() {
+            positions[*v as usize] = i;
+        }
+        positions
+    }
+
+    /// The goal with its blank walked to the bottom right corner, where [`Board::solve`] has to leave it, and the
+    /// moves that walk it back again
+    pub(crate) fn staged_goal(&self) -> (Array2D<u32>, Vec<Move>) {
+        let mut staged = Board::new(self.goal.clone());
+        let [x, y] = staged.blank_pos;
+        let walk: Vec<Move> = std::iter::repeat_n(Move::Right, self.cols() - 1 - x)
+            .chain(std::iter::repeat_n(Move::Down, self.rows() - 1 - y))
+            .collect();
+        for m in &walk {
+            staged.slide(*m).unwrap();
+        }
+        let back = walk.iter().rev().map(|m| m.inverse()).collect();
+        (staged.board, back)
+    }
+}
+
+/// Whether the parity of the layout's tile permutation, together with the blank's row on even width boards, is
+/// odd. Moves never change it, so two layouts can only reach each other when it is the same for both.
+pub(crate) fn parity(layout: &Array2D<u32>) -> bool {
+    let tiles: Vec<u32> = layout
+        .elements_row_major_iter()
+        .copied()
+        .filter(|i| *i != 0)
+        .collect();
+    let mut inversions = 0;
+    for (i, a) in tiles.iter().enumerate() {
+        inversions += tiles[i + 1..].iter().filter(|b| *b < a).count();
+    }
+    if layout.num_columns() % 2 == 1 {
+        inversions % 2 == 1
+    } else {
+        let blank = layout
+            .elements_row_major_iter()
+            .position(|v| *v == 0)
+            .unwrap();
+        let row_from_bottom = layout.num_rows() - blank / layout.num_columns();
+        (inversions + row_from_bottom) % 2 == 1
+    }
+}
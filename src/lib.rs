@@ -1,12 +1,28 @@
-use std::collections::VecDeque;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::Display;
+use std::str::FromStr;
 
 use array2d::Array2D;
 
+mod goal;
+mod optimal;
+mod pattern;
+mod rng;
+mod scramble;
+pub mod search;
+mod sequence;
 mod soccer_puzzle;
-pub use soccer_puzzle::Game as SoccerPuzzle;
-
-#[derive(Debug, Clone, Copy, PartialEq)]
+pub use goal::{Goal, SolvedCheck};
+pub use pattern::{PatternDatabase, PatternError};
+pub use search::{Monitor, Puzzle, SearchStats};
+pub use sequence::{MoveSequence, ParseMoveError};
+pub use soccer_puzzle::{
+    Block, BlockedMove, Direction, Game as SoccerPuzzle, LayoutError, Metric, Move as PieceMove,
+    NoSolution, ParseGameError, ParsePieceMoveError, Placement, Replay, Shape, Shift,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Move {
     Left,
     Right,
@@ -14,19 +30,130 @@ pub enum Move {
     Down,
 }
 
+impl Move {
+    /// Returns the move that undoes this one
+    pub fn inverse(self) -> Self {
+        match self {
+            Move::Left => Move::Right,
+            Move::Right => Move::Left,
+            Move::Up => Move::Down,
+            Move::Down => Move::Up,
+        }
+    }
+}
+
+/// Error returned when a board can't reach the solved position from its current layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unsolvable;
+
+impl Display for Unsolvable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("board is not solvable: tile permutation has the wrong parity")
+    }
+}
+
+impl std::error::Error for Unsolvable {}
+
+/// Error returned when a move would take the blank off the edge of the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IllegalMove(pub Move);
+
+impl Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "cannot move the blank {:?} off the edge of the board",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
+/// Reasons a layout can't be turned into a Board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardError {
+    /// The board has no rows or no columns
+    Empty,
+    /// The rows of the board have different lengths
+    NotRectangular,
+    /// The board is smaller than the solver supports
+    TooSmall { rows: usize, cols: usize },
+    /// There is no 0 tile to act as the blank
+    MissingBlank,
+    /// The tile appears more than once
+    DuplicateTile(u32),
+    /// The tile is not in `0..rows * cols`
+    OutOfRange(u32),
+    /// The goal layout is this size, which is not the size of the board
+    GoalSize { rows: usize, cols: usize },
+}
+
+impl Display for BoardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoardError::Empty => f.write_str("board is empty"),
+            BoardError::NotRectangular => f.write_str("board rows have different lengths"),
+            BoardError::TooSmall { rows, cols } => {
+                write!(f, "board is {}x{}, must be at least 2x2", rows, cols)
+            }
+            BoardError::MissingBlank => f.write_str("board has no blank (0) tile"),
+            BoardError::DuplicateTile(v) => write!(f, "tile {} appears more than once", v),
+            BoardError::OutOfRange(v) => write!(f, "tile {} is out of range for the board size", v),
+            BoardError::GoalSize { rows, cols } => {
+                write!(f, "goal is {}x{}, not the size of the board", rows, cols)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
+/// Error returned when a board printed by Board's `Display` can't be read back. Lines and columns count from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseBoardError {
+    /// The text starting here isn't a tile number
+    InvalidTile { line: usize, column: usize },
+    /// The tiles were read but don't make a valid board
+    Board(BoardError),
+}
+
+impl Display for ParseBoardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseBoardError::InvalidTile { line, column } => {
+                write!(
+                    f,
+                    "line {}, column {}: expected a tile number",
+                    line, column
+                )
+            }
+            ParseBoardError::Board(e) => write!(f, "invalid board: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ParseBoardError {}
+
 #[derive(Debug, Clone)]
 pub struct Board {
     board: Array2D<u32>,
     blank_pos: [usize; 2],
     moves: Vec<Move>,
+    /// Layout the solvers work towards
+    goal: Array2D<u32>,
 }
 
 impl Display for Board {
+    /// Right-aligns every tile to the width of the largest one plus a space, and to no less than 3 columns, so
+    /// neighbouring tiles never run together
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let largest = self.board.num_elements() - 1;
+        let width = (largest.to_string().len() + 1).max(3);
         let mut s = String::new();
         for row in self.board.rows_iter() {
             for i in row {
-                s += &format!("{:3}", i);
+                s += &format!("{:width$}", i);
             }
             s += "\n";
         }
@@ -34,98 +161,138 @@ impl Display for Board {
     }
 }
 
+impl FromStr for Board {
+    type Err = ParseBoardError;
+
+    /// Reads a board in the format printed by `Display`, one row per line with the tiles separated by whitespace.
+    /// Blank lines before and after the board are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rows = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut row = Vec::new();
+            let mut rest = line;
+            while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+                let column = line.len() - rest.len() + start + 1;
+                let len = rest[start..]
+                    .find(char::is_whitespace)
+                    .unwrap_or(rest.len() - start);
+                let tile =
+                    rest[start..start + len]
+                        .parse()
+                        .map_err(|_| ParseBoardError::InvalidTile {
+                            line: i + 1,
+                            column,
+                        })?;
+                row.push(tile);
+                rest = &rest[start + len..];
+            }
+            rows.push(row);
+        }
+        if rows.is_empty() {
+            return Err(ParseBoardError::Board(BoardError::Empty));
+        }
+        Board::from_rows(&rows).map_err(ParseBoardError::Board)
+    }
+}
+
 impl Board {
+    /// Creates a Board from a layout, panicking if it isn't valid. See [`Board::try_new`].
     pub fn new(board: Array2D<u32>) -> Self {
-        let mut blank_pos = [0, 0];
-        for (i, v) in board.elements_row_major_iter().enumerate() {
-            if *v == 0 {
-                blank_pos = [i % board.row_len(), i / board.row_len()];
-                break;
+        match Self::try_new(board) {
+            Ok(board) => board,
+            Err(e) => panic!("invalid board: {}", e),
+        }
+    }
+
+    /// Creates a Board from a layout containing each of the tiles `0..rows * cols` exactly once, 0 being the blank
+    pub fn try_new(board: Array2D<u32>) -> Result<Self, BoardError> {
+        let (rows, cols) = (board.column_len(), board.row_len());
+        if rows == 0 || cols == 0 {
+            return Err(BoardError::Empty);
+        }
+        if rows < 2 || cols < 2 {
+            return Err(BoardError::TooSmall { rows, cols });
+        }
+
+        let blank = board
+            .elements_row_major_iter()
+            .position(|v| *v == 0)
+            .ok_or(BoardError::MissingBlank)?;
+        let blank_pos = [blank % cols, blank / cols];
+
+        let mut seen = vec![false; rows * cols];
+        for v in board.elements_row_major_iter() {
+            match seen.get_mut(*v as usize) {
+                None => return Err(BoardError::OutOfRange(*v)),
+                Some(true) => return Err(BoardError::DuplicateTile(*v)),
+                Some(s) => *s = true,
             }
         }
-        Self {
+
+        Ok(Self {
             board,
             blank_pos,
             moves: Vec::new(),
-        }
+            goal: Goal::Standard.layout(rows, cols),
+        })
     }
 
-    fn make_move(&mut self, m: Move) {
-        match m {
-            Move::Left => {
-                if self.blank_pos[0] > 0 {
-                    self.board
-                        .set(
-                            self.blank_pos[1],
-                            self.blank_pos[0],
-                            *self
-                                .board
-                                .get(self.blank_pos[1], self.blank_pos[0] - 1)
-                                .unwrap(),
-                        )
-                        .unwrap();
-                    self.board
-                        .set(self.blank_pos[1], self.blank_pos[0] - 1, 0)
-                        .unwrap();
-                    self.blank_pos[0] -= 1;
-                }
-            }
-            Move::Right => {
-                if self.blank_pos[0] < self.board.row_len() - 1 {
-                    self.board
-                        .set(
-                            self.blank_pos[1],
-                            self.blank_pos[0],
-                            *self
-                                .board
-                                .get(self.blank_pos[1], self.blank_pos[0] + 1)
-                                .unwrap(),
-                        )
-                        .unwrap();
-                    self.board
-                        .set(self.blank_pos[1], self.blank_pos[0] + 1, 0)
-                        .unwrap();
-                    self.blank_pos[0] += 1;
-                }
-            }
-            Move::Up => {
-                if self.blank_pos[1] > 0 {
-                    self.board
-                        .set(
-                            self.blank_pos[1],
-                            self.blank_pos[0],
-                            *self
-                                .board
-                                .get(self.blank_pos[1] - 1, self.blank_pos[0])
-                                .unwrap(),
-                        )
-                        .unwrap();
-                    self.board
-                        .set(self.blank_pos[1] - 1, self.blank_pos[0], 0)
-                        .unwrap();
-                    self.blank_pos[1] -= 1;
-                }
-            }
-            Move::Down => {
-                if self.blank_pos[1] < self.board.column_len() - 1 {
-                    self.board
-                        .set(
-                            self.blank_pos[1],
-                            self.blank_pos[0],
-                            *self
-                                .board
-                                .get(self.blank_pos[1] + 1, self.blank_pos[0])
-                                .unwrap(),
-                        )
-                        .unwrap();
-                    self.board
-                        .set(self.blank_pos[1] + 1, self.blank_pos[0], 0)
-                        .unwrap();
-                    self.blank_pos[1] += 1;
-                }
-            }
-        }
+    /// Creates a Board from a list of rows, see [`Board::try_new`]
+    pub fn from_rows(rows: &[Vec<u32>]) -> Result<Self, BoardError> {
+        let board = Array2D::from_rows(rows).map_err(|_| BoardError::NotRectangular)?;
+        Self::try_new(board)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.board.column_len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.board.row_len()
+    }
+
+    /// Moves the blank in the direction of `m`, recording the move.
+    ///
+    /// Returns an error and leaves the board untouched if the blank is already against that edge.
+    pub fn apply(&mut self, m: Move) -> Result<(), IllegalMove> {
+        self.slide(m)?;
         self.moves.push(m);
+        Ok(())
+    }
+
+    /// Moves the blank can make from the current position
+    pub fn legal_moves(&self) -> Vec<Move> {
+        [Move::Up, Move::Down, Move::Left, Move::Right]
+            .into_iter()
+            .filter(|m| self.step(self.blank_pos, *m).is_some())
+            .collect()
+    }
+
+    /// Takes back the last move, returning it, or `None` if no moves have been made
+    pub fn undo(&mut self) -> Option<Move> {
+        let m = self.moves.pop()?;
+        self.slide(m.inverse())
+            .expect("the inverse of an applied move is always legal");
+        Some(m)
+    }
+
+    /// Swaps the blank with the tile next to it without recording the move
+    fn slide(&mut self, m: Move) -> Result<(), IllegalMove> {
+        let [x, y] = self.step(self.blank_pos, m).ok_or(IllegalMove(m))?;
+        let tile = *self.board.get(y, x).unwrap();
+        self.board
+            .set(self.blank_pos[1], self.blank_pos[0], tile)
+            .unwrap();
+        self.board.set(y, x, 0).unwrap();
+        self.blank_pos = [x, y];
+        Ok(())
+    }
+
+    fn make_move(&mut self, m: Move) {
+        self.apply(m).expect("solvers only make legal moves");
     }
 
     fn find(&self, val: u32) -> Result<[usize; 2], u32> {
@@ -136,318 +303,450 @@ impl Board {
                 }
             }
         }
-        return Err(val);
+        Err(val)
     }
 
-    fn move_piece(&mut self, piece: u32, mut dest: [usize; 2]) {
-        let mut piece_start = self.find(piece).unwrap();
-
-        // If our destination is the right edge of the board, we will instead move it to the second column from the right
-        // and shift left as our final move
-        let mut final_left = false;
-        if dest[0] == self.board.row_len() - 1 {
-            dest[0] -= 1;
-            final_left = true;
+    /// Position next to `pos` in the direction of the move, if it is on the board
+    fn step(&self, pos: [usize; 2], m: Move) -> Option<[usize; 2]> {
+        let [x, y] = pos;
+        match m {
+            Move::Left if x > 0 => Some([x - 1, y]),
+            Move::Right if x < self.board.row_len() - 1 => Some([x + 1, y]),
+            Move::Up if y > 0 => Some([x, y - 1]),
+            Move::Down if y < self.board.column_len() - 1 => Some([x, y + 1]),
+            _ => None,
         }
+    }
 
-        // Standardize the position we are starting from: place the blank tile to the right of the tile being moved
-        // This isn't necessarily the most efficient technique, if we are moving a piece to the far right column
-        // of the board, then we could have unnecessary swaps
-
-        // If the piece and the blank are in the same column
-        if piece_start[0] == self.blank_pos[0] {
-            // If the blank isn't on the right edge, move it to the column to the right of the piece
-            if piece_start[0] != self.board.row_len() - 1 {
-                self.make_move(Move::Right);
-                if piece_start[1] > self.blank_pos[1] {
-                    for _ in 0..piece_start[1] - self.blank_pos[1] {
-                        self.make_move(Move::Down);
-                    }
-                } else {
-                    for _ in 0..self.blank_pos[1] - piece_start[1] {
-                        self.make_move(Move::Up);
-                    }
-                }
-            }
-            // If the blank is on the right edge, want to get it to the left of the piece then move right
-            else {
-                // If the piece is above the blank, then simply move left, then up, then right
-                if piece_start[1] < self.blank_pos[1] {
-                    self.make_move(Move::Left);
-                    for _ in 0..self.blank_pos[1] - piece_start[1] {
-                        self.make_move(Move::Up);
-                    }
-                    self.make_move(Move::Right);
-                    piece_start[0] -= 1;
-                }
-                // If the piece is below the blank, we want to go down 1 if we can
-                else if piece_start[1] > self.blank_pos[1] + 1 {
-                    self.make_move(Move::Down);
-                    self.make_move(Move::Left);
-                    for _ in 0..piece_start[1] - self.blank_pos[1] - 1 {
-                        self.make_move(Move::Down);
-                    }
-                    self.make_move(Move::Right);
-                    piece_start[0] -= 1;
-                }
-                // Otherwise assume an initial left is safe
-                else {
-                    self.make_move(Move::Left);
-                    for _ in 0..piece_start[1] - self.blank_pos[1] {
-                        self.make_move(Move::Down);
-                    }
-                    self.make_move(Move::Right);
-                    piece_start[0] -= 1;
-                }
-            }
-        } else {
-            // If the blank is above the piece, move it down into the same row
-            if piece_start[1] > self.blank_pos[1] {
-                for _ in 0..piece_start[1] - self.blank_pos[1] {
-                    self.make_move(Move::Down);
+    /// The tile that belongs at the given position in the goal
+    fn goal_tile(&self, pos: [usize; 2]) -> u32 {
+        *self.goal.get(pos[1], pos[0]).unwrap()
+    }
+
+    /// Breadth-first search for the shortest list of moves after which `done` holds for the positions of the blank
+    /// followed by each of `tiles`. The blank never enters a locked cell, so the tiles in them are left alone.
+    fn route(
+        &self,
+        tiles: &[u32],
+        locked: &Array2D<bool>,
+        done: impl Fn(&[[usize; 2]]) -> bool,
+        monitor: &mut Monitor,
+    ) -> Option<Vec<Move>> {
+        let cells = (self.board.row_len() * self.board.column_len()) as u128;
+        let encode = |positions: &[[usize; 2]]| {
+            positions.iter().rev().fold(0, |key, [x, y]| {
+                key * cells + (y * self.board.row_len() + x) as u128
+            })
+        };
+
+        let mut start = vec![self.blank_pos];
+        start.extend(tiles.iter().map(|t| self.find(*t).unwrap()));
+        // Each state is kept as a key with its parent, and as a list of positions while it is queued
+        let node_size = std::mem::size_of::<(u128, Option<(u128, Move)>)>()
+            + std::mem::size_of::<Vec<[usize; 2]>>()
+            + std::mem::size_of_val(start.as_slice());
+        let mut parents = HashMap::new();
+        parents.insert(encode(&start), None);
+        let mut queue = VecDeque::from([(start, 0)]);
+        while let Some((positions, depth)) = queue.pop_front() {
+            let key = encode(&positions);
+            if done(&positions) {
+                let mut moves = Vec::new();
+                let mut key = key;
+                while let Some(Some((parent, m))) = parents.get(&key) {
+                    moves.push(*m);
+                    key = *parent;
                 }
+                moves.reverse();
+                return Some(moves);
             }
-            // If the blank is to the right of the piece, move to the square to the right
-            if self.blank_pos[0] > piece_start[0] {
-                for _ in 0..self.blank_pos[0] - piece_start[0] - 1 {
-                    self.make_move(Move::Left);
+            monitor.expanded(depth);
+            for m in [Move::Up, Move::Down, Move::Left, Move::Right] {
+                let Some(blank) = self.step(positions[0], m) else {
+                    continue;
+                };
+                if *locked.get(blank[1], blank[0]).unwrap() {
+                    continue;
                 }
-                // If the blank is below the piece, move it up to the same row
-                for _ in 0..self.blank_pos[1] - piece_start[1] {
-                    self.make_move(Move::Up);
-                }
-            } else {
-                // If the blank is to the left of the piece, move it so it is to the left of the piece
-                for _ in 0..piece_start[0] - self.blank_pos[0] - 1 {
-                    self.make_move(Move::Right);
+                monitor.generated();
+                let mut next = positions.clone();
+                // A tracked tile in the way slides back into where the blank was
+                if let Some(moved) = next[1..].iter_mut().find(|p| **p == blank) {
+                    *moved = positions[0];
                 }
-                // If the blank is below the piece, move it up to the same row
-                for _ in 0..self.blank_pos[1] - piece_start[1] {
-                    self.make_move(Move::Up);
+                next[0] = blank;
+                let next_key = encode(&next);
+                if let Entry::Vacant(e) = parents.entry(next_key) {
+                    e.insert(Some((key, m)));
+                    queue.push_back((next, depth + 1));
+                } else {
+                    monitor.pruned();
                 }
-                // Swap the blank and the piece
-                self.make_move(Move::Right);
-                piece_start[0] -= 1;
             }
+            monitor.holding(queue.len(), parents.len() * node_size);
         }
+        None
+    }
 
-        // Figure out what directions the piece needs to be moved in
-        let mut r = 0;
-        let mut l = 0;
-        let mut u = 0;
-        let mut d = 0;
-
-        if piece_start[0] > dest[0] {
-            l = piece_start[0] - dest[0];
-        } else {
-            r = dest[0] - piece_start[0];
+    /// Moves `piece` to `dest` without disturbing any locked tiles.
+    ///
+    /// Walks the piece there with [`Board::walk_piece`], which only ever searches near the piece, and falls back to
+    /// a search over every position of the piece and the blank where the walk gets stuck.
+    fn move_piece(
+        &mut self,
+        piece: u32,
+        dest: [usize; 2],
+        locked: &Array2D<bool>,
+        monitor: &mut Monitor,
+    ) {
+        if self.walk_piece(piece, dest, locked, monitor) {
+            return;
         }
-
-        if piece_start[1] > dest[1] {
-            u = piece_start[1] - dest[1];
-        } else {
-            d = dest[1] - piece_start[1];
+        let moves = self
+            .route(&[piece], locked, |p| p[1] == dest, monitor)
+            .expect("unlocked part of the board should be connected");
+        for m in moves {
+            self.make_move(m);
         }
+    }
 
-        // Ignoring diagonal moves for now
-        // let ur = cmp::min(u, r);
-        // u -= ur;
-        // r -= ur;
-
-        // let ul = cmp::min(u, l);
-        // u -= ul;
-        // l -= ul;
+    /// Slides `piece` one square at a time along a shortest path to `dest`, each time bringing the blank round to the
+    /// square ahead of it without touching the piece or any locked tile. Returns false if the blank can't get round,
+    /// leaving the moves made so far.
+    fn walk_piece(
+        &mut self,
+        piece: u32,
+        dest: [usize; 2],
+        locked: &Array2D<bool>,
+        monitor: &mut Monitor,
+    ) -> bool {
+        let moves = [Move::Up, Move::Down, Move::Left, Move::Right];
+        // Squares away from the destination through unlocked cells
+        let mut distance =
+            Array2D::filled_with(usize::MAX, locked.num_rows(), locked.num_columns());
+        distance.set(dest[1], dest[0], 0).unwrap();
+        let mut queue = VecDeque::from([dest]);
+        while let Some(pos) = queue.pop_front() {
+            let d = distance[(pos[1], pos[0])];
+            for next in moves.iter().filter_map(|m| self.step(pos, *m)) {
+                if !locked[(next[1], next[0])] && distance[(next[1], next[0])] == usize::MAX {
+                    distance.set(next[1], next[0], d + 1).unwrap();
+                    queue.push_back(next);
+                }
+            }
+        }
 
-        // // This should always be 0
-        // let dr = cmp::min(d, r);
-        // d -= dr;
-        // r -= dr;
+        let mut avoid = locked.clone();
+        let mut pos = self.find(piece).unwrap();
+        while pos != dest {
+            let blank = self.blank_pos;
+            let Some(ahead) = moves
+                .iter()
+                .filter_map(|m| self.step(pos, *m))
+                .filter(|p| distance[(p[1], p[0])] < distance[(pos[1], pos[0])])
+                .min_by_key(|p| p[0].abs_diff(blank[0]) + p[1].abs_diff(blank[1]))
+            else {
+                return false;
+            };
+            avoid.set(pos[1], pos[0], true).unwrap();
+            let route = self.route(&[], &avoid, |p| p[0] == ahead, monitor);
+            avoid.set(pos[1], pos[0], false).unwrap();
+            let Some(route) = route else {
+                return false;
+            };
+            for m in route {
+                self.make_move(m);
+            }
+            // Swap the blank with the piece behind it
+            let back = moves
+                .into_iter()
+                .find(|m| self.step(ahead, *m) == Some(pos));
+            self.make_move(back.unwrap());
+            pos = ahead;
+        }
+        true
+    }
 
-        // let dl = cmp::min(d, l);
-        // d -= dl;
-        // l -= dl;
+    /// Places the last two tiles of a row or column, where `ends` are the last two cells of the line and `inward`
+    /// points into the unsolved part of the board.
+    ///
+    /// Putting the first tile in directly would leave nowhere to slide the second one in from, so instead the first
+    /// tile is parked at the end of the line with the second next to it, then both are rotated into place inside
+    /// the 2x3 window at the end of the line.
+    fn solve_line_end(
+        &mut self,
+        ends: [[usize; 2]; 2],
+        inward: Move,
+        locked: &mut Array2D<bool>,
+        monitor: &mut Monitor,
+    ) {
+        let [first, last] = ends;
+        let (a, b) = (self.goal_tile(first), self.goal_tile(last));
+        if self.find(a) != Ok(first) || self.find(b) != Ok(last) {
+            self.move_piece(a, last, locked, monitor);
+            locked.set(last[1], last[0], true).unwrap();
+            // The cell a is meant to end up in is now a dead end. b can only fail to get out when it is caught in or
+            // right next to it, both of which are already inside the window.
+            let beside = self.step(last, inward).unwrap();
+            if !self.walk_piece(b, beside, locked, monitor) {
+                if let Some(moves) = self.route(&[b], locked, |p| p[1] == beside, monitor) {
+                    for m in moves {
+                        self.make_move(m);
+                    }
+                }
+            }
+            locked.set(last[1], last[0], false).unwrap();
 
-        let on_bottom = piece_start[1] == self.board.column_len() - 1;
+            let mut window = Vec::new();
+            for end in ends {
+                let mid = self.step(end, inward).unwrap();
+                window.extend([end, mid, self.step(mid, inward).unwrap()]);
+            }
 
-        // Move order: right, up, down, left
+            // Bring the blank into the window without knocking a or b back out
+            let (pos_a, pos_b) = (self.find(a).unwrap(), self.find(b).unwrap());
+            let mut avoid = locked.clone();
+            avoid.set(pos_a[1], pos_a[0], true).unwrap();
+            avoid.set(pos_b[1], pos_b[0], true).unwrap();
+            let moves = self
+                .route(&[], &avoid, |p| window.contains(&p[0]), monitor)
+                .unwrap();
+            for m in moves {
+                self.make_move(m);
+            }
 
-        for _ in 0..r {
-            // Move right
-            self.make_move(Move::Left);
-            if on_bottom {
-                self.make_move(Move::Up);
-            } else {
-                self.make_move(Move::Down);
+            let mut outside = Array2D::filled_with(true, locked.num_rows(), locked.num_columns());
+            for [x, y] in &window {
+                outside.set(*y, *x, false).unwrap();
             }
-            self.make_move(Move::Right);
-            self.make_move(Move::Right);
-            if on_bottom {
-                self.make_move(Move::Down);
-            } else {
-                self.make_move(Move::Up);
+            let moves = self
+                .route(
+                    &[a, b],
+                    &outside,
+                    |p| p[1] == first && p[2] == last,
+                    monitor,
+                )
+                .expect("any layout of two tiles and the blank in a 2x3 window can be solved");
+            for m in moves {
+                self.make_move(m);
             }
         }
+        locked.set(first[1], first[0], true).unwrap();
+        locked.set(last[1], last[0], true).unwrap();
+    }
 
-        for _ in 0..u {
-            // Move up
-            self.make_move(Move::Up);
-            self.make_move(Move::Left);
-            self.make_move(Move::Down);
-            self.make_move(Move::Right);
-            self.make_move(Move::Up);
+    /// Solves the top row of the unsolved region starting at `[left, top]`
+    fn solve_row(
+        &mut self,
+        top: usize,
+        left: usize,
+        locked: &mut Array2D<bool>,
+        monitor: &mut Monitor,
+    ) {
+        let cols = self.board.row_len();
+        for x in left..cols - 2 {
+            self.move_piece(self.goal_tile([x, top]), [x, top], locked, monitor);
+            locked.set(top, x, true).unwrap();
         }
+        self.solve_line_end(
+            [[cols - 2, top], [cols - 1, top]],
+            Move::Down,
+            locked,
+            monitor,
+        );
+    }
 
-        for _ in 0..d {
-            // Move down
-            self.make_move(Move::Down);
-            self.make_move(Move::Left);
-            self.make_move(Move::Up);
-            self.make_move(Move::Right);
-            self.make_move(Move::Down);
+    /// Solves the left column of the unsolved region starting at `[left, top]`
+    fn solve_col(
+        &mut self,
+        top: usize,
+        left: usize,
+        locked: &mut Array2D<bool>,
+        monitor: &mut Monitor,
+    ) {
+        let rows = self.board.column_len();
+        for y in top..rows - 2 {
+            self.move_piece(self.goal_tile([left, y]), [left, y], locked, monitor);
+            locked.set(y, left, true).unwrap();
         }
+        self.solve_line_end(
+            [[left, rows - 2], [left, rows - 1]],
+            Move::Right,
+            locked,
+            monitor,
+        );
+    }
 
-        let on_bottom = dest[1] == self.board.column_len() - 1;
-        // If moving left, we do it from the left side of the piece
-        // Change to move from bottom: left is interfering
-        if l > 0 {
-            self.make_move(Move::Left);
-            for _ in 0..l {
-                // Move left
-                self.make_move(Move::Right);
-                if on_bottom {
-                    self.make_move(Move::Up);
-                } else {
-                    self.make_move(Move::Down);
+    /// Finishes the last 2x2 or 2x3 region left by the reduction with an exhaustive search, leaving the blank
+    /// where the goal has it too
+    fn brute_force_corner(&mut self, locked: &Array2D<bool>, monitor: &mut Monitor) {
+        let mut tiles = Vec::new();
+        let mut dests = Vec::new();
+        let mut blank = None;
+        for (y, row) in locked.rows_iter().enumerate() {
+            for (x, l) in row.enumerate() {
+                let tile = self.goal_tile([x, y]);
+                if *l {
+                    continue;
                 }
-                self.make_move(Move::Left);
-                self.make_move(Move::Left);
-                if on_bottom {
-                    self.make_move(Move::Down);
+                if tile == 0 {
+                    blank = Some([x, y]);
                 } else {
-                    self.make_move(Move::Up);
+                    tiles.push(tile);
+                    dests.push([x, y]);
                 }
             }
-            self.make_move(Move::Right);
         }
-
-        if final_left {
-            self.make_move(Move::Left);
+        let moves = self
+            .route(
+                &tiles,
+                locked,
+                |p| Some(p[0]) == blank && p[1..] == dests[..],
+                monitor,
+            )
+            .expect("solvable boards always reduce to a solvable corner");
+        for m in moves {
+            self.make_move(m);
         }
     }
 
-    fn solve_row(&mut self, row: usize) {
-        for piece in 1 + row * self.board.row_len() + row..(row + 1) * self.board.row_len() - 1 {
-            self.move_piece(piece as u32, [(piece - 1) % self.board.row_len(), row]);
+    /// Checks whether the board matches the goal exactly, blank included
+    pub fn check_solved(&self) -> bool {
+        self.check_solved_with(SolvedCheck::Strict)
+    }
+
+    /// Checks whether the board matches the goal, ignoring where the blank is if the check allows it
+    pub fn check_solved_with(&self, check: SolvedCheck) -> bool {
+        match check {
+            SolvedCheck::Strict => self.board == self.goal,
+            SolvedCheck::IgnoreBlank => {
+                let tiles = |layout: &Array2D<u32>| {
+                    layout
+                        .elements_row_major_iter()
+                        .copied()
+                        .filter(|i| *i != 0)
+                        .collect::<Vec<u32>>()
+                };
+                tiles(&self.board) == tiles(&self.goal)
+            }
         }
-        self.move_piece(
-            ((row + 1) * self.board.row_len() - 1) as u32,
-            [self.board.row_len() - 1, row],
-        );
-        self.move_piece(
-            ((row + 1) * self.board.row_len()) as u32,
-            [self.board.row_len() - 1, row + 1],
-        );
-        self.make_move(Move::Up);
-        self.make_move(Move::Right);
-        self.make_move(Move::Down);
     }
 
-    fn solve_col(&mut self, col: usize) {
-        for piece in (self.board.row_len() * (col + 1) + col + 1
-            ..self.board.row_len() * (self.board.column_len() - 2) + col + 1)
-            .step_by(self.board.row_len())
-        {
-            self.move_piece(piece as u32, [col, piece / self.board.row_len()]);
+    /// The moves made so far as a string like `L,R,U,`, which can be parsed back into a [`MoveSequence`]
+    pub fn print_moves(&self) -> String {
+        self.move_sequence().to_string()
+    }
+
+    /// Checks whether the board can be moved into the goal layout.
+    ///
+    /// Every move changes the blank's row or swaps it within one, so the number of inversions (pairs of tiles in
+    /// the wrong row-major order) only keeps its parity when the blank's row is accounted for. On boards with an
+    /// odd width a vertical move passes the tile over an even number of others, so only the inversion count
+    /// matters. The board can be solved when this parity is the same as the goal's.
+    pub fn is_solvable(&self) -> bool {
+        goal::parity(&self.board) == goal::parity(&self.goal)
+    }
+
+    /// Solves the board by placing one row or column at a time until only a small corner is left.
+    ///
+    /// Reduces whichever dimension is longer so that any board down to 2x2 can be handled, then finishes the last
+    /// 2x2 or 2x3 region exactly. Tiles are walked into place with small searches around them, so even large boards
+    /// are quick, but the solution is far from the shortest. The moves are cleaned up with
+    /// [`MoveSequence::simplify`] afterwards, and the number of moves that saved is returned.
+    ///
+    /// The reduction always finishes in the bottom right corner, so it solves towards the goal with the blank
+    /// walked there, then walks the blank back.
+    pub fn solve(&mut self) -> Result<usize, Unsolvable> {
+        self.solve_monitored(&mut Monitor::new())
+    }
+
+    /// [`Board::solve`], recording the searches that place each tile in `monitor`
+    pub fn solve_monitored(&mut self, monitor: &mut Monitor) -> Result<usize, Unsolvable> {
+        if !self.is_solvable() {
+            return Err(Unsolvable);
         }
-        let last = self.board.row_len() * (self.board.column_len() - 1) + 1 + col;
-        let second_last = last - self.board.row_len();
-        self.move_piece(second_last as u32, [col, self.board.column_len() - 1]);
-        self.move_piece(last as u32, [col + 1, self.board.column_len() - 1]);
-        self.make_move(Move::Up);
-        self.make_move(Move::Left);
-        self.make_move(Move::Left);
-        self.make_move(Move::Down);
-        self.make_move(Move::Right);
-    }
-
-    fn brute_force_corner(&mut self) {
-        let mut a_star = VecDeque::new();
-        a_star.push_back((self.moves.last().unwrap().clone(), self.clone()));
+        let start = self.moves.len();
+        let (staged, back) = self.staged_goal();
+        let goal = std::mem::replace(&mut self.goal, staged);
+        let (rows, cols) = (self.board.column_len(), self.board.row_len());
+        let mut locked = Array2D::filled_with(false, rows, cols);
+        let (mut top, mut left) = (0, 0);
         loop {
-            let (prev, start) = a_star.pop_front().unwrap();
-            // println!("{}", start);
-            if prev != Move::Down && start.blank_pos[1] >= self.board.column_len() - 2 {
-                let mut up = start.clone();
-                up.make_move(Move::Up);
-                if up.check_solved() {
-                    *self = up;
-                    return;
-                } else {
-                    a_star.push_back((Move::Up, up));
-                }
-            }
-            if prev != Move::Up && start.blank_pos[1] <= self.board.column_len() - 2 {
-                let mut down = start.clone();
-                down.make_move(Move::Down);
-                if down.check_solved() {
-                    *self = down;
-                    return;
-                } else {
-                    a_star.push_back((Move::Down, down));
-                }
-            }
-            if prev != Move::Left && start.blank_pos[0] <= self.board.row_len() - 2 {
-                let mut right = start.clone();
-                right.make_move(Move::Right);
-                if right.check_solved() {
-                    *self = right;
-                    return;
-                } else {
-                    a_star.push_back((Move::Right, right));
-                }
+            let (height, width) = (rows - top, cols - left);
+            if height.min(width) <= 2 && height.max(width) <= 3 {
+                break;
             }
-            if prev != Move::Right && start.blank_pos[0] >= self.board.row_len() - 2 {
-                let mut left = start.clone();
-                left.make_move(Move::Left);
-                if left.check_solved() {
-                    *self = left;
-                    return;
-                } else {
-                    a_star.push_back((Move::Left, left));
-                }
+            if height >= width {
+                self.solve_row(top, left, &mut locked, monitor);
+                top += 1;
+            } else {
+                self.solve_col(top, left, &mut locked, monitor);
+                left += 1;
             }
         }
+        self.brute_force_corner(&locked, monitor);
+        monitor.finished();
+        self.goal = goal;
+        for m in back {
+            self.make_move(m);
+        }
+        debug_assert!(self.check_solved());
+
+        let mut solution = MoveSequence::from(self.moves.split_off(start));
+        let saved = solution.simplify();
+        self.moves.extend(solution);
+        Ok(saved)
     }
+}
 
-    fn check_solved(&self) -> bool {
-        self.board
-            .as_row_major()
+impl Puzzle for Board {
+    /// Tiles in row-major order
+    type State = Vec<u32>;
+    type Move = Move;
+
+    fn state(&self) -> Vec<u32> {
+        self.board.as_row_major()
+    }
+
+    fn successors(&self, state: &Vec<u32>) -> Vec<(Move, Vec<u32>)> {
+        let width = self.board.row_len();
+        let blank = state.iter().position(|t| *t == 0).unwrap();
+        [Move::Up, Move::Down, Move::Left, Move::Right]
             .into_iter()
-            .filter(|i| *i != 0)
-            .collect::<Vec<u32>>()
-            .as_slice()
-            .windows(2)
-            .all(|w| w[0] < w[1])
+            .filter_map(|m| {
+                let [x, y] = self.step([blank % width, blank / width], m)?;
+                let mut next = state.clone();
+                next.swap(blank, y * width + x);
+                Some((m, next))
+            })
+            .collect()
     }
 
-    pub fn print_moves(&self) -> String {
-        let mut s = String::new();
-        for m in self.moves.iter() {
-            match m {
-                Move::Left => s += "L,",
-                Move::Right => s += "R,",
-                Move::Up => s += "U,",
-                Move::Down => s += "D,",
-            }
-        }
-        s
+    /// Only the goal layout itself counts, blank included
+    fn is_goal(&self, state: &Vec<u32>) -> bool {
+        state.iter().eq(self.goal.elements_row_major_iter())
     }
 
-    pub fn solve(&mut self) {
-        for i in 0..self.board.column_len() - 3 {
-            self.solve_row(i);
-            self.solve_col(i);
-        }
-        self.brute_force_corner();
+    /// Sum of the manhattan distances of every tile from where it belongs
+    fn heuristic(&self, state: &Vec<u32>) -> u32 {
+        let width = self.board.row_len();
+        let goal_positions = self.goal_positions();
+        state
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| **t != 0)
+            .map(|(i, t)| {
+                let goal = goal_positions[*t as usize];
+                ((i % width).abs_diff(goal % width) + (i / width).abs_diff(goal / width)) as u32
+            })
+            .sum()
+    }
+
+    fn apply(&mut self, m: Move) {
+        self.make_move(m);
+    }
+
+    fn state_size(&self, state: &Vec<u32>) -> usize {
+        std::mem::size_of::<Vec<u32>>() + std::mem::size_of_val(state.as_slice())
     }
 }
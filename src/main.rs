@@ -1,26 +1,483 @@
-use array2d::Array2D;
+use std::io::Read;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
 use sliding_puzzle_solver::*;
 
-fn main() {
-    // // some move is not working for this board, need to check it out later
-    // let rows = vec![vec![16, 12, 7, 0, 5], vec![6, 10, 4, 1, 9], vec![21, 11, 24, 19, 3], vec![17, 14, 2, 18, 15], vec![13, 23, 22, 8, 20]];
-    // // let rows = vec![vec![1, 2, 3, 4, 5], vec![6, 7, 8, 9, 10], vec![11, 12, 13, 14, 15], vec![16, 17, 18, 19, 20], vec![21, 22, 23, 24, 0]];
-    // let board_array = Array2D::from_rows(&rows).unwrap();
-    // let mut board = Board::new(board_array);
-
-    // println!("{}", board);
-    // board.solve();
-    // println!("{}", board);
-    // println!("{}", board.print_moves());
-
-    let mut g = SoccerPuzzle::new();
-    println!("{}", g);
-    let start = std::time::Instant::now();
-    g.solve();
-    println!("{:?}", start.elapsed());
-    // let mut demo = SoccerPuzzle::new();
-    // for m in g.moves {
-    //     demo.make_move(m);
-    //     println!("{}", demo);
-    // }
+const USAGE: &str = "usage: sliding-puzzle-solver <command> <board|soccer> [options] [FILE]
+
+Puzzles are read from FILE, or from stdin if it is missing or `-`, in the format they are printed in.
+
+commands:
+  solve      solve the puzzle and print the moves
+  verify     check that --moves solves the puzzle, exiting with 1 if it doesn't
+  scramble   print a random solvable board of --size, or a scrambled soccer puzzle starting from FILE
+  play       solve the puzzle and show every position on the way
+  bench      time solving the puzzle --runs times
+  pdb        build pattern databases for boards of --size and write them to FILE
+
+options:
+  --optimal        find a shortest solution for a board instead of a quick one
+  --pdb <FILE>     pattern databases to guide --optimal, built with the pdb command
+  --pattern <N>    tiles in each pattern database [default: 6]
+  --goal <GOAL>    layout to solve boards towards: standard, blank-first, snake or spiral [default: standard]
+  --slide          count sliding a soccer piece any distance as one move
+  --moves <MOVES>  moves to verify, like `L3U2` for a board or `4DL 2U` for soccer
+  --stats          print how much searching solving took
+  --progress <N>   print search progress every N expanded nodes
+  --ignore-blank   let verify accept a board with the tiles in order wherever the blank is
+  --size <RxC>     size of the board to scramble or build databases for [default: 4x4]
+  --steps <N>      scramble with this many random moves instead, starting from a solved board
+                   [default for soccer: 200]
+  --seed <N>       seed for scrambling [default: based on the time]
+  --animate        redraw each position in place instead of printing them all
+  --delay <MS>     milliseconds between positions when animating [default: 300]
+  --runs <N>       number of solves to time [default: 5]";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Solve,
+    Verify,
+    Scramble,
+    Play,
+    Bench,
+    Pdb,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Board,
+    Soccer,
+}
+
+struct Options {
+    command: Command,
+    kind: Kind,
+    file: Option<String>,
+    optimal: bool,
+    stats: bool,
+    progress: Option<u64>,
+    pdb: Option<String>,
+    pattern: usize,
+    goal: Goal,
+    slide: bool,
+    moves: Option<String>,
+    check: SolvedCheck,
+    size: (usize, usize),
+    steps: Option<usize>,
+    seed: Option<u64>,
+    animate: bool,
+    delay: Duration,
+    runs: usize,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let command = match args.next().as_deref() {
+            Some("solve") => Command::Solve,
+            Some("verify") => Command::Verify,
+            Some("scramble") => Command::Scramble,
+            Some("play") => Command::Play,
+            Some("bench") => Command::Bench,
+            Some("pdb") => Command::Pdb,
+            Some(other) => return Err(format!("unknown command {:?}", other)),
+            None => return Err("no command given".to_string()),
+        };
+        let kind = match args.next().as_deref() {
+            Some("board") => Kind::Board,
+            Some("soccer") => Kind::Soccer,
+            Some(other) => {
+                return Err(format!(
+                    "unknown puzzle {:?}, expected board or soccer",
+                    other
+                ))
+            }
+            None => return Err("no puzzle given, expected board or soccer".to_string()),
+        };
+        let mut options = Options {
+            command,
+            kind,
+            file: None,
+            optimal: false,
+            stats: false,
+            progress: None,
+            pdb: None,
+            pattern: 6,
+            goal: Goal::Standard,
+            slide: false,
+            moves: None,
+            check: SolvedCheck::Strict,
+            size: (4, 4),
+            steps: None,
+            seed: None,
+            animate: false,
+            delay: Duration::from_millis(300),
+            runs: 5,
+        };
+
+        fn value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+            let value = value.ok_or(format!("{} needs a value", flag))?;
+            value
+                .parse()
+                .map_err(|_| format!("invalid value {:?} for {}", value, flag))
+        }
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--optimal" => options.optimal = true,
+                "--stats" => options.stats = true,
+                "--progress" => options.progress = Some(value(&arg, args.next())?),
+                "--slide" => options.slide = true,
+                "--animate" => options.animate = true,
+                "--ignore-blank" => options.check = SolvedCheck::IgnoreBlank,
+                "--moves" => options.moves = Some(value(&arg, args.next())?),
+                "--pdb" => options.pdb = Some(value(&arg, args.next())?),
+                "--pattern" => options.pattern = value(&arg, args.next())?,
+                "--goal" => {
+                    let goal: String = value(&arg, args.next())?;
+                    options.goal = match goal.as_str() {
+                        "standard" => Goal::Standard,
+                        "blank-first" => Goal::BlankFirst,
+                        "snake" => Goal::Snake,
+                        "spiral" => Goal::Spiral,
+                        _ => return Err(format!("unknown goal {:?}", goal)),
+                    };
+                }
+                "--steps" => options.steps = Some(value(&arg, args.next())?),
+                "--seed" => options.seed = Some(value(&arg, args.next())?),
+                "--runs" => options.runs = value(&arg, args.next())?,
+                "--delay" => options.delay = Duration::from_millis(value(&arg, args.next())?),
+                "--size" => {
+                    let size: String = value(&arg, args.next())?;
+                    let parsed = size
+                        .split_once('x')
+                        .and_then(|(r, c)| Some((r.parse().ok()?, c.parse().ok()?)));
+                    options.size = parsed.ok_or(format!(
+                        "invalid size {:?}, expected something like 4x4",
+                        size
+                    ))?;
+                }
+                flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+                _ if options.file.is_some() => {
+                    return Err(format!("unexpected argument {:?}", arg))
+                }
+                _ => options.file = Some(arg),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Text of the puzzle to work on
+    fn read_input(&self) -> Result<String, String> {
+        match self.file.as_deref() {
+            None | Some("-") => {
+                let mut text = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut text)
+                    .map_err(|e| format!("can't read stdin: {}", e))?;
+                Ok(text)
+            }
+            Some(path) => {
+                std::fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path, e))
+            }
+        }
+    }
+
+    fn read_board(&self) -> Result<Board, String> {
+        let mut board: Board = self
+            .read_input()?
+            .parse()
+            .map_err(|e: ParseBoardError| e.to_string())?;
+        let goal = self.goal.layout(board.rows(), board.cols());
+        board.set_goal(goal).map_err(|e| e.to_string())?;
+        Ok(board)
+    }
+
+    fn read_soccer(&self) -> Result<SoccerPuzzle, String> {
+        let mut game: SoccerPuzzle = self
+            .read_input()?
+            .parse()
+            .map_err(|e: ParseGameError| e.to_string())?;
+        if self.slide {
+            game.metric = Metric::Slide;
+        }
+        Ok(game)
+    }
+
+    /// Pattern databases from --pdb, checked against the board they will be used for
+    fn read_pdb(&self, board: &Board) -> Result<Option<PatternDatabase>, String> {
+        let Some(path) = self.pdb.as_deref() else {
+            return Ok(None);
+        };
+        if !self.optimal {
+            return Err("--pdb needs --optimal".to_string());
+        }
+        let db = PatternDatabase::load(path).map_err(|e| format!("can't read {}: {}", path, e))?;
+        if (db.rows(), db.cols()) != (board.rows(), board.cols()) {
+            return Err(format!(
+                "{} is for a {}x{} board",
+                path,
+                db.rows(),
+                db.cols()
+            ));
+        }
+        if db.goal() != board.goal() {
+            return Err(format!("{} is for a different --goal", path));
+        }
+        Ok(Some(db))
+    }
+
+    /// Monitor for a solve, printing progress if asked to
+    fn monitor(&self) -> Monitor<'static> {
+        match self.progress {
+            Some(every) => Monitor::with_progress(every, |stats| eprintln!("... {}", stats)),
+            None => Monitor::new(),
+        }
+    }
+
+    /// Prints the stats of a finished solve if asked to
+    fn report(&self, monitor: &Monitor) {
+        if self.stats {
+            eprintln!("{}", monitor.stats());
+        }
+    }
+
+    fn solve_board(
+        &self,
+        board: &mut Board,
+        db: Option<&PatternDatabase>,
+        monitor: &mut Monitor,
+    ) -> Result<(), String> {
+        let solved = if self.optimal {
+            board.solve_optimal_monitored(db, monitor)
+        } else {
+            board.solve_monitored(monitor).map(|_| ())
+        };
+        solved.map_err(|e| e.to_string())
+    }
+}
+
+fn solve(options: &Options) -> Result<ExitCode, String> {
+    let start = Instant::now();
+    match options.kind {
+        Kind::Board => {
+            let mut board = options.read_board()?;
+            let db = options.read_pdb(&board)?;
+            let mut monitor = options.monitor();
+            options.solve_board(&mut board, db.as_ref(), &mut monitor)?;
+            options.report(&monitor);
+            println!("{}", board.print_moves());
+            eprintln!(
+                "{} moves in {:?}",
+                board.move_sequence().len(),
+                start.elapsed()
+            );
+        }
+        Kind::Soccer => {
+            let mut game = options.read_soccer()?;
+            let mut monitor = options.monitor();
+            game.solve_monitored(&mut monitor)
+                .map_err(|e| e.to_string())?;
+            options.report(&monitor);
+            println!("{}", game.print_moves());
+            eprintln!("{} moves in {:?}", game.moves.len(), start.elapsed());
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn verify(options: &Options) -> Result<ExitCode, String> {
+    let moves = options.moves.as_deref().ok_or("verify needs --moves")?;
+    let solved = match options.kind {
+        Kind::Board => {
+            let mut board = options.read_board()?;
+            let moves: MoveSequence = moves.parse().map_err(|e: ParseMoveError| e.to_string())?;
+            board.apply_sequence(&moves).map_err(|e| e.to_string())?;
+            board.check_solved_with(options.check)
+        }
+        Kind::Soccer => {
+            let mut game = options.read_soccer()?;
+            let moves = PieceMove::parse_list(moves).map_err(|e| e.to_string())?;
+            game.apply_moves(&moves).map_err(|e| e.to_string())?;
+            game.is_goal(&game.state())
+        }
+    };
+    if solved {
+        println!("solved");
+        Ok(ExitCode::SUCCESS)
+    } else {
+        println!("not solved");
+        Ok(ExitCode::from(1))
+    }
+}
+
+fn scramble(options: &Options) -> Result<ExitCode, String> {
+    let seed = options.seed.unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(1, |d| d.as_nanos() as u64)
+    });
+    eprintln!("seed {}", seed);
+    match options.kind {
+        Kind::Board => {
+            let (rows, cols) = options.size;
+            let goal = options.goal.layout(rows, cols);
+            let board = match options.steps {
+                Some(steps) => Board::try_new(goal).map(|mut board| {
+                    board.random_walk(steps, seed);
+                    board
+                }),
+                None => Board::scrambled_towards(goal, seed),
+            };
+            print!("{}", board.map_err(|e| e.to_string())?);
+        }
+        Kind::Soccer => {
+            let mut game = if options.file.is_some() {
+                options.read_soccer()?
+            } else {
+                SoccerPuzzle::new()
+            };
+            game.random_walk(options.steps.unwrap_or(200), seed);
+            print!("{}", game);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints a position, clearing the screen first and pausing afterwards when animating
+fn show(options: &Options, title: &str, position: &str) {
+    if options.animate {
+        // Clear the screen and go back to the top left corner
+        print!("\x1b[2J\x1b[H");
+    }
+    println!("{}\n{}", title, position);
+    if options.animate {
+        std::thread::sleep(options.delay);
+    }
+}
+
+fn play(options: &Options) -> Result<ExitCode, String> {
+    match options.kind {
+        Kind::Board => {
+            let start = options.read_board()?;
+            let mut board = start.clone();
+            let db = options.read_pdb(&board)?;
+            let mut monitor = options.monitor();
+            options.solve_board(&mut board, db.as_ref(), &mut monitor)?;
+            options.report(&monitor);
+            let mut position = start;
+            show(options, "Start", &position.to_string());
+            for (i, m) in board.move_sequence().into_iter().enumerate() {
+                position.apply(m).unwrap();
+                show(
+                    options,
+                    &format!("Move {}: {}", i + 1, m),
+                    &position.to_string(),
+                );
+            }
+        }
+        Kind::Soccer => {
+            let mut game = options.read_soccer()?;
+            let mut monitor = options.monitor();
+            game.solve_monitored(&mut monitor)
+                .map_err(|e| e.to_string())?;
+            options.report(&monitor);
+            for (i, frame) in game.replay().enumerate() {
+                match frame.moves.last() {
+                    Some(m) => show(
+                        options,
+                        &format!("Move {}: {}", i, frame.show_move(m)),
+                        &frame.highlighted(m.piece),
+                    ),
+                    None => show(options, "Start", &frame.to_string()),
+                }
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn bench(options: &Options) -> Result<ExitCode, String> {
+    let runs = options.runs.max(1);
+    let mut times = Vec::with_capacity(runs);
+    let mut moves = 0;
+    // Every run searches the same way, so the stats of the last one are the stats of them all
+    let mut monitor = options.monitor();
+    match options.kind {
+        Kind::Board => {
+            let board = options.read_board()?;
+            let db = options.read_pdb(&board)?;
+            for _ in 0..runs {
+                let mut board = board.clone();
+                monitor = options.monitor();
+                let start = Instant::now();
+                options.solve_board(&mut board, db.as_ref(), &mut monitor)?;
+                times.push(start.elapsed());
+                moves = board.move_sequence().len();
+            }
+        }
+        Kind::Soccer => {
+            let game = options.read_soccer()?;
+            for _ in 0..runs {
+                let mut game = game.clone();
+                monitor = options.monitor();
+                let start = Instant::now();
+                game.solve_monitored(&mut monitor)
+                    .map_err(|e| e.to_string())?;
+                times.push(start.elapsed());
+                moves = game.moves.len();
+            }
+        }
+    }
+    let total: Duration = times.iter().sum();
+    println!(
+        "{} runs, {} moves, min {:?}, mean {:?}, max {:?}",
+        runs,
+        moves,
+        times.iter().min().unwrap(),
+        total / runs as u32,
+        times.iter().max().unwrap()
+    );
+    options.report(&monitor);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn pdb(options: &Options) -> Result<ExitCode, String> {
+    if options.kind != Kind::Board {
+        return Err("pattern databases are only for boards".to_string());
+    }
+    let path = options
+        .file
+        .as_deref()
+        .ok_or("pdb needs a FILE to write to")?;
+    let (rows, cols) = options.size;
+    let start = Instant::now();
+    let patterns = PatternDatabase::split(rows, cols, options.pattern);
+    let goal = options.goal.layout(rows, cols);
+    let db = PatternDatabase::build_for_goal(&goal, &patterns).map_err(|e| e.to_string())?;
+    db.save(path)
+        .map_err(|e| format!("can't write {}: {}", path, e))?;
+    eprintln!("built in {:?}", start.elapsed());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let result = match options.command {
+        Command::Solve => solve(&options),
+        Command::Verify => verify(&options),
+        Command::Scramble => scramble(&options),
+        Command::Play => play(&options),
+        Command::Bench => bench(&options),
+        Command::Pdb => pdb(&options),
+    };
+    result.unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        ExitCode::from(2)
+    })
 }
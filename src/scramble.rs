@@ -0,0 +1,66 @@
+use array2d::Array2D;
+
+use crate::rng::Rng;
+use crate::{Board, BoardError, Goal, Move};
+
+impl Board {
+    /// The solved rows x cols board, with the tiles in order and the blank in the bottom right corner
+    pub fn solved(rows: usize, cols: usize) -> Result<Self, BoardError> {
+        let len = rows * cols;
+        let tiles: Vec<u32> = (1..=len).map(|i| (i % len) as u32).collect();
+        Self::from_tiles(&tiles, rows, cols)
+    }
+
+    fn from_tiles(tiles: &[u32], rows: usize, cols: usize) -> Result<Self, BoardError> {
+        let board = Array2D::from_row_major(tiles, rows, cols).map_err(|_| BoardError::Empty)?;
+        Self::try_new(board)
+    }
+
+    /// A rows x cols board picked uniformly at random from every solvable one, the same each time for a given seed
+    pub fn scrambled(rows: usize, cols: usize, seed: u64) -> Result<Self, BoardError> {
+        Self::scrambled_towards(Goal::Standard.layout(rows, cols), seed)
+    }
+
+    /// A board picked uniformly at random from every one that can reach `goal`, with that goal set. The same each
+    /// time for a given seed.
+    pub fn scrambled_towards(goal: Array2D<u32>, seed: u64) -> Result<Self, BoardError> {
+        let (rows, cols) = (goal.num_rows(), goal.num_columns());
+        let len = rows * cols;
+        let mut rng = Rng::new(seed);
+        let mut tiles: Vec<u32> = (0..len as u32).collect();
+        for i in (1..len).rev() {
+            tiles.swap(i, rng.below(i + 1));
+        }
+        let mut board = Self::from_tiles(&tiles, rows, cols)?;
+        board.set_goal(goal.clone())?;
+        if board.is_solvable() {
+            return Ok(board);
+        }
+        // Swapping two tiles flips solvability, and pairs every unsolvable board with exactly one solvable one, so
+        // the result is still uniform
+        let mut nonzero = (0..len).filter(|i| tiles[*i] != 0);
+        let (a, b) = (nonzero.next().unwrap(), nonzero.next().unwrap());
+        tiles.swap(a, b);
+        let mut board = Self::from_tiles(&tiles, rows, cols)?;
+        board.set_goal(goal)?;
+        Ok(board)
+    }
+
+    /// Scrambles the board by moving the blank `n_moves` times at random, never straight back to where it just
+    /// was, so more moves usually make a harder board. The moves aren't recorded. The same each time for a given
+    /// seed.
+    pub fn random_walk(&mut self, n_moves: usize, seed: u64) {
+        let mut rng = Rng::new(seed);
+        let mut last: Option<Move> = None;
+        for _ in 0..n_moves {
+            let moves: Vec<Move> = self
+                .legal_moves()
+                .into_iter()
+                .filter(|m| Some(m.inverse()) != last)
+                .collect();
+            let m = moves[rng.below(moves.len())];
+            self.slide(m).unwrap();
+            last = Some(m);
+        }
+    }
+}
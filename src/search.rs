@@ -0,0 +1,346 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fmt::Display;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// A puzzle that can be solved by searching through its states
+///
+/// The state holds everything that changes as moves are made, while anything fixed, like the size of the board, can
+/// stay in the puzzle itself. Every move costs the same.
+pub trait Puzzle {
+    type State: Clone + Eq + Hash;
+    type Move: Copy;
+
+    /// The state the puzzle is currently in
+    fn state(&self) -> Self::State;
+
+    /// Every move that can be made from `state`, along with the state it leads to
+    fn successors(&self, state: &Self::State) -> Vec<(Self::Move, Self::State)>;
+
+    fn is_goal(&self, state: &Self::State) -> bool;
+
+    /// Lower bound on the number of moves from `state` to a goal. A* and IDA* only find the shortest solution if
+    /// this never overestimates.
+    fn heuristic(&self, _state: &Self::State) -> u32 {
+        0
+    }
+
+    /// Makes a move on the puzzle itself, recording it in its history
+    fn apply(&mut self, m: Self::Move);
+
+    /// Bytes a state takes up, including anything it owns on the heap, used to estimate how much memory a search
+    /// needs
+    fn state_size(&self, _state: &Self::State) -> usize {
+        std::mem::size_of::<Self::State>()
+    }
+}
+
+/// How much work a search did, see [`Monitor`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchStats {
+    /// States whose successors were generated
+    pub nodes_expanded: u64,
+    /// Successor states generated, including the ones that were pruned
+    pub nodes_generated: u64,
+    /// Most states waiting to be expanded at once, or the longest path for depth-first searches
+    pub max_frontier: usize,
+    /// Successors skipped because they had already been reached at least as cheaply
+    pub duplicates_pruned: u64,
+    /// Most moves from the start to an expanded state
+    pub max_depth: usize,
+    /// Time from creating the monitor to the end of the last search
+    pub elapsed: Duration,
+    /// Rough estimate of the most memory the searches' states took up at once, in bytes
+    pub peak_memory: usize,
+}
+
+impl Display for SearchStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} expanded, {} generated, {} pruned, max frontier {}, max depth {}, ~{} KiB, {:?}",
+            self.nodes_expanded,
+            self.nodes_generated,
+            self.duplicates_pruned,
+            self.max_frontier,
+            self.max_depth,
+            self.peak_memory.div_ceil(1024),
+            self.elapsed
+        )
+    }
+}
+
+/// Called with the stats so far while a search runs
+type Progress<'a> = Box<dyn FnMut(&SearchStats) + 'a>;
+
+/// Collects [`SearchStats`] while searches run, and reports them every so often if asked to.
+///
+/// One monitor can be passed to several searches in turn, and adds up what they all did.
+pub struct Monitor<'a> {
+    stats: SearchStats,
+    start: Instant,
+    progress: Option<Progress<'a>>,
+    /// Expanded nodes between calls to `progress`
+    every: u64,
+    /// Number of expanded nodes at which to call `progress` next
+    next_report: u64,
+}
+
+impl Default for Monitor<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Monitor<'a> {
+    pub fn new() -> Self {
+        Self {
+            stats: SearchStats::default(),
+            start: Instant::now(),
+            progress: None,
+            every: u64::MAX,
+            next_report: u64::MAX,
+        }
+    }
+
+    /// A monitor that calls `progress` with the stats so far after every `every` expanded nodes
+    pub fn with_progress(every: u64, progress: impl FnMut(&SearchStats) + 'a) -> Self {
+        Self {
+            progress: Some(Box::new(progress)),
+            every: every.max(1),
+            next_report: every.max(1),
+            ..Self::new()
+        }
+    }
+
+    /// The stats so far
+    pub fn stats(&self) -> SearchStats {
+        self.stats
+    }
+
+    pub(crate) fn expanded(&mut self, depth: usize) {
+        self.stats.nodes_expanded += 1;
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+        if self.stats.nodes_expanded >= self.next_report {
+            self.next_report = self.next_report.saturating_add(self.every);
+            self.stats.elapsed = self.start.elapsed();
+            if let Some(progress) = &mut self.progress {
+                progress(&self.stats);
+            }
+        }
+    }
+
+    pub(crate) fn generated(&mut self) {
+        self.stats.nodes_generated += 1;
+    }
+
+    pub(crate) fn pruned(&mut self) {
+        self.stats.duplicates_pruned += 1;
+    }
+
+    /// Records the size of the frontier and the memory taken up by every state being kept
+    pub(crate) fn holding(&mut self, frontier: usize, bytes: usize) {
+        self.stats.max_frontier = self.stats.max_frontier.max(frontier);
+        self.stats.peak_memory = self.stats.peak_memory.max(bytes);
+    }
+
+    /// Marks the end of a search
+    pub(crate) fn finished(&mut self) {
+        self.stats.elapsed = self.start.elapsed();
+    }
+}
+
+/// Follows parent pointers back from `node` to build the moves that lead to it
+fn path<M: Copy>(parents: &[Option<(usize, M)>], mut node: usize) -> Vec<M> {
+    let mut moves = Vec::new();
+    while let Some((parent, m)) = parents[node] {
+        moves.push(m);
+        node = parent;
+    }
+    moves.reverse();
+    moves
+}
+
+/// Breadth-first search for a shortest solution, or `None` if no goal can be reached
+pub fn bfs<P: Puzzle>(puzzle: &P) -> Option<Vec<P::Move>> {
+    bfs_monitored(puzzle, &mut Monitor::new())
+}
+
+/// [`bfs`], recording what it does in `monitor`
+pub fn bfs_monitored<P: Puzzle>(puzzle: &P, monitor: &mut Monitor) -> Option<Vec<P::Move>> {
+    let solution = bfs_inner(puzzle, monitor);
+    monitor.finished();
+    solution
+}
+
+fn bfs_inner<P: Puzzle>(puzzle: &P, monitor: &mut Monitor) -> Option<Vec<P::Move>> {
+    let start = puzzle.state();
+    if puzzle.is_goal(&start) {
+        return Some(Vec::new());
+    }
+    // Each state is kept as a key and in the queue, along with its parent
+    let node_size = 2 * puzzle.state_size(&start) + std::mem::size_of::<Option<(usize, P::Move)>>();
+    let mut parents = vec![None];
+    let mut seen = HashMap::from([(start.clone(), 0)]);
+    let mut queue = VecDeque::from([(start, 0, 0)]);
+    while let Some((state, node, depth)) = queue.pop_front() {
+        monitor.expanded(depth);
+        for (m, next) in puzzle.successors(&state) {
+            monitor.generated();
+            if let Entry::Vacant(e) = seen.entry(next.clone()) {
+                let child = parents.len();
+                parents.push(Some((node, m)));
+                e.insert(child);
+                if puzzle.is_goal(&next) {
+                    return Some(path(&parents, child));
+                }
+                queue.push_back((next, child, depth + 1));
+            } else {
+                monitor.pruned();
+            }
+        }
+        monitor.holding(queue.len(), parents.len() * node_size);
+    }
+    None
+}
+
+/// A* search guided by [`Puzzle::heuristic`], or `None` if no goal can be reached
+pub fn a_star<P: Puzzle>(puzzle: &P) -> Option<Vec<P::Move>> {
+    a_star_monitored(puzzle, &mut Monitor::new())
+}
+
+/// [`a_star`], recording what it does in `monitor`
+pub fn a_star_monitored<P: Puzzle>(puzzle: &P, monitor: &mut Monitor) -> Option<Vec<P::Move>> {
+    let solution = a_star_inner(puzzle, monitor);
+    monitor.finished();
+    solution
+}
+
+fn a_star_inner<P: Puzzle>(puzzle: &P, monitor: &mut Monitor) -> Option<Vec<P::Move>> {
+    let start = puzzle.state();
+    // Each state is kept in the list and as a key, along with its parent, cost and a few entries in the queue
+    let node_size = 2 * puzzle.state_size(&start)
+        + std::mem::size_of::<(Option<(usize, P::Move)>, u32, usize)>()
+        + 2 * std::mem::size_of::<(u32, u32, usize)>();
+    let mut states = vec![start.clone()];
+    let mut parents = vec![None];
+    let mut costs = vec![0];
+    let mut seen = HashMap::from([(start.clone(), 0)]);
+    // Ordered by estimated total cost, then by the heuristic so that states closer to the goal go first, then the
+    // most recently found
+    let h = puzzle.heuristic(&start);
+    let mut open = BinaryHeap::from([Reverse((h, h, Reverse(0)))]);
+    while let Some(Reverse((f, h, Reverse(node)))) = open.pop() {
+        let cost = costs[node];
+        if f > cost + h {
+            // A cheaper way to this state was found after it was queued
+            monitor.pruned();
+            continue;
+        }
+        let state = states[node].clone();
+        if puzzle.is_goal(&state) {
+            return Some(path(&parents, node));
+        }
+        monitor.expanded(cost as usize);
+        for (m, next) in puzzle.successors(&state) {
+            monitor.generated();
+            let child = match seen.entry(next) {
+                Entry::Occupied(e) => {
+                    let child = *e.get();
+                    if costs[child] <= cost + 1 {
+                        monitor.pruned();
+                        continue;
+                    }
+                    child
+                }
+                Entry::Vacant(e) => {
+                    let child = states.len();
+                    states.push(e.key().clone());
+                    parents.push(None);
+                    costs.push(0);
+                    e.insert(child);
+                    child
+                }
+            };
+            parents[child] = Some((node, m));
+            costs[child] = cost + 1;
+            let h = puzzle.heuristic(&states[child]);
+            open.push(Reverse((cost + 1 + h, h, Reverse(child))));
+        }
+        monitor.holding(open.len(), states.len() * node_size);
+    }
+    None
+}
+
+/// Result of searching below a cost bound in [`ida_star`]
+enum Outcome {
+    Found,
+    /// Holds the smallest estimated cost that went over the bound, if any did
+    Exceeded(Option<u32>),
+}
+
+/// Depth-first search from the last state in `states`, keeping every state on the current path so loops are skipped
+fn bounded<P: Puzzle>(
+    puzzle: &P,
+    states: &mut Vec<P::State>,
+    moves: &mut Vec<P::Move>,
+    bound: u32,
+    monitor: &mut Monitor,
+) -> Outcome {
+    let state = states.last().unwrap();
+    let f = moves.len() as u32 + puzzle.heuristic(state);
+    if f > bound {
+        return Outcome::Exceeded(Some(f));
+    }
+    if puzzle.is_goal(state) {
+        return Outcome::Found;
+    }
+    monitor.expanded(moves.len());
+    let mut next_bound = None;
+    for (m, next) in puzzle.successors(state) {
+        monitor.generated();
+        if states.contains(&next) {
+            monitor.pruned();
+            continue;
+        }
+        let held = states.len() + 1;
+        monitor.holding(held, held * puzzle.state_size(&next));
+        states.push(next);
+        moves.push(m);
+        match bounded(puzzle, states, moves, bound, monitor) {
+            Outcome::Found => return Outcome::Found,
+            Outcome::Exceeded(Some(f)) => {
+                next_bound = Some(next_bound.map_or(f, |b: u32| b.min(f)));
+            }
+            Outcome::Exceeded(None) => {}
+        }
+        states.pop();
+        moves.pop();
+    }
+    Outcome::Exceeded(next_bound)
+}
+
+/// Iterative deepening A*, which finds the same solutions as [`a_star`] while only keeping the current path in
+/// memory, or `None` if no goal can be reached
+pub fn ida_star<P: Puzzle>(puzzle: &P) -> Option<Vec<P::Move>> {
+    ida_star_monitored(puzzle, &mut Monitor::new())
+}
+
+/// [`ida_star`], recording what it does in `monitor`. Every state expanded again in a later iteration counts again.
+pub fn ida_star_monitored<P: Puzzle>(puzzle: &P, monitor: &mut Monitor) -> Option<Vec<P::Move>> {
+    let start = puzzle.state();
+    let mut bound = puzzle.heuristic(&start);
+    let mut states = vec![start];
+    let mut moves = Vec::new();
+    let solution = loop {
+        match bounded(puzzle, &mut states, &mut moves, bound, monitor) {
+            Outcome::Found => break Some(moves),
+            Outcome::Exceeded(Some(next)) => bound = next,
+            Outcome::Exceeded(None) => break None,
+        }
+    };
+    monitor.finished();
+    solution
+}
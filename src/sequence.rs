@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::rng::mix;
+use crate::{Board, IllegalMove, Move};
+
+/// Error returned when a move or list of moves can't be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMoveError {
+    /// There was nothing to parse
+    Empty,
+    /// The character at `pos` is not L, R, U or D, or a count following one of them
+    UnknownMove { pos: usize, found: char },
+    /// The count starting at `pos` is zero or too large
+    InvalidCount { pos: usize },
+}
+
+impl Display for ParseMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseMoveError::Empty => f.write_str("no move given"),
+            ParseMoveError::UnknownMove { pos, found } => {
+                write!(
+                    f,
+                    "unexpected {:?} at position {}, expected L, R, U or D",
+                    found, pos
+                )
+            }
+            ParseMoveError::InvalidCount { pos } => {
+                write!(f, "invalid repeat count at position {}", pos)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseMoveError {}
+
+impl Move {
+    fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_uppercase() {
+            'L' => Some(Move::Left),
+            'R' => Some(Move::Right),
+            'U' => Some(Move::Up),
+            'D' => Some(Move::Down),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Move::Left => "L",
+            Move::Right => "R",
+            Move::Up => "U",
+            Move::Down => "D",
+        })
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    /// Parses a single letter, L, R, U or D in either case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.trim().char_indices();
+        let (_, c) = chars.next().ok_or(ParseMoveError::Empty)?;
+        let m = Move::from_char(c).ok_or(ParseMoveError::UnknownMove { pos: 0, found: c })?;
+        match chars.next() {
+            Some((pos, found)) => Err(ParseMoveError::UnknownMove { pos, found }),
+            None => Ok(m),
+        }
+    }
+}
+
+/// A list of moves, written the same way as [`Board::print_moves`]
+///
+/// Displays as `L,R,U,` and parses that format back, as well as the compact `LRUD` and run-length `L3U2` forms.
+/// Commas and whitespace between moves are optional and ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MoveSequence {
+    moves: Vec<Move>,
+}
+
+impl MoveSequence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn push(&mut self, m: Move) {
+        self.moves.push(m);
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Move> {
+        self.moves.iter()
+    }
+
+    /// Shortens the sequence without changing where it leads, returning how many moves were removed.
+    ///
+    /// Moves that are immediately undone are dropped first, then the sequence is replayed to find positions that
+    /// are reached more than once, and everything between the first and last visit is cut out.
+    pub fn simplify(&mut self) -> usize {
+        let before = self.moves.len();
+        let mut moves: Vec<Move> = Vec::with_capacity(before);
+        for m in self.moves.drain(..) {
+            if moves.last() == Some(&m.inverse()) {
+                moves.pop();
+            } else {
+                moves.push(m);
+            }
+        }
+
+        let mut grid = Grid::default();
+        let mut hashes = vec![grid.hash];
+        for m in &moves {
+            grid.apply(*m);
+            hashes.push(grid.hash);
+        }
+        let last_seen: HashMap<u64, usize> =
+            hashes.iter().enumerate().map(|(i, h)| (*h, i)).collect();
+
+        let mut i = 0;
+        while i < moves.len() {
+            let j = last_seen[&hashes[i]];
+            // Hashes can collide, so check the moves in between really do lead back to the same position
+            if j > i && Grid::is_loop(&moves[i..j]) {
+                i = j;
+                continue;
+            }
+            self.moves.push(moves[i]);
+            i += 1;
+        }
+        before - self.moves.len()
+    }
+}
+
+/// Unbounded board that a sequence of moves can be replayed on without knowing the real board, tracking the
+/// position reached by which cell each tile started in, relative to the blank's starting cell
+#[derive(Default)]
+struct Grid {
+    blank: (i32, i32),
+    /// Cell each displaced tile started in, cells missing from the map still hold their original tile
+    contents: HashMap<(i32, i32), (i32, i32)>,
+    /// Zobrist hash of `contents`, 0 for the starting position
+    hash: u64,
+}
+
+impl Grid {
+    fn content(&self, cell: (i32, i32)) -> (i32, i32) {
+        *self.contents.get(&cell).unwrap_or(&cell)
+    }
+
+    fn set(&mut self, cell: (i32, i32), content: (i32, i32)) {
+        let old = self.content(cell);
+        if old != cell {
+            self.hash ^= zobrist(cell, old);
+        }
+        if content != cell {
+            self.hash ^= zobrist(cell, content);
+            self.contents.insert(cell, content);
+        } else {
+            self.contents.remove(&cell);
+        }
+    }
+
+    fn apply(&mut self, m: Move) {
+        let (x, y) = self.blank;
+        let next = match m {
+            Move::Left => (x - 1, y),
+            Move::Right => (x + 1, y),
+            Move::Up => (x, y - 1),
+            Move::Down => (x, y + 1),
+        };
+        let (tile, blank) = (self.content(next), self.content(self.blank));
+        self.set(self.blank, tile);
+        self.set(next, blank);
+        self.blank = next;
+    }
+
+    /// Checks whether the moves end up back in the position they started from
+    fn is_loop(moves: &[Move]) -> bool {
+        let mut grid = Grid::default();
+        for m in moves {
+            grid.apply(*m);
+        }
+        grid.contents.is_empty()
+    }
+}
+
+/// Pseudo-random value for a tile that started in `content` being in `cell`
+fn zobrist(cell: (i32, i32), content: (i32, i32)) -> u64 {
+    let pack = |(x, y): (i32, i32)| ((x as u32 as u64) << 32) | y as u32 as u64;
+    mix(pack(cell) ^ mix(pack(content)))
+}
+
+impl From<Vec<Move>> for MoveSequence {
+    fn from(moves: Vec<Move>) -> Self {
+        Self { moves }
+    }
+}
+
+impl From<MoveSequence> for Vec<Move> {
+    fn from(seq: MoveSequence) -> Self {
+        seq.moves
+    }
+}
+
+impl FromIterator<Move> for MoveSequence {
+    fn from_iter<I: IntoIterator<Item = Move>>(iter: I) -> Self {
+        Self {
+            moves: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for MoveSequence {
+    type Item = Move;
+    type IntoIter = std::vec::IntoIter<Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveSequence {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves.iter()
+    }
+}
+
+impl Display for MoveSequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for m in &self.moves {
+            write!(f, "{},", m)?;
+        }
+        Ok(())
+    }
+}
+
+/// Largest repeat count accepted after a move. No board is anywhere near this wide, so anything bigger is a typo,
+/// and refusing it keeps a short string from asking for an enormous sequence.
+const MAX_COUNT: usize = 1 << 16;
+
+impl FromStr for MoveSequence {
+    type Err = ParseMoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut moves = Vec::new();
+        let mut chars = s.char_indices().peekable();
+        while let Some((pos, c)) = chars.next() {
+            if c == ',' || c.is_whitespace() {
+                continue;
+            }
+            let m = Move::from_char(c).ok_or(ParseMoveError::UnknownMove { pos, found: c })?;
+
+            // An optional count after the move repeats it
+            let mut digits = String::new();
+            let count_pos = pos + c.len_utf8();
+            while let Some((_, d)) = chars.next_if(|(_, d)| d.is_ascii_digit()) {
+                digits.push(d);
+            }
+            let count = if digits.is_empty() {
+                1
+            } else {
+                match digits.parse::<usize>() {
+                    Ok(n) if (1..=MAX_COUNT).contains(&n) => n,
+                    _ => return Err(ParseMoveError::InvalidCount { pos: count_pos }),
+                }
+            };
+            moves.extend(std::iter::repeat_n(m, count));
+        }
+        Ok(Self { moves })
+    }
+}
+
+impl Board {
+    /// The moves made on this board so far
+    pub fn move_sequence(&self) -> MoveSequence {
+        MoveSequence::from(self.moves.clone())
+    }
+
+    /// Applies every move in the sequence in order.
+    ///
+    /// Stops at the first move that would take the blank off the board, leaving the moves before it applied.
+    pub fn apply_sequence(&mut self, seq: &MoveSequence) -> Result<(), IllegalMove> {
+        for m in seq {
+            self.apply(*m)?;
+        }
+        Ok(())
+    }
+}
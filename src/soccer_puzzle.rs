@@ -1,218 +1,1247 @@
-use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+use std::str::FromStr;
 
 use array2d::Array2D;
 
-#[derive(Copy, Clone, Debug, Eq, derivative::Derivative)]
-#[derivative(PartialEq, Hash)]
-enum Piece {
-    Tall(
-        #[derivative(PartialEq = "ignore")]
-        #[derivative(Hash = "ignore")]
-        usize,
-    ),
+use crate::rng::Rng;
+use crate::search::{self, Monitor, Puzzle};
+
+/// Direction a piece moves in
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    /// The square one step away from `[row, col]` in this direction, if it isn't off the top or left edge
+    fn step(self, [row, col]: [usize; 2]) -> Option<[usize; 2]> {
+        match self {
+            Direction::Up => Some([row.checked_sub(1)?, col]),
+            Direction::Down => Some([row + 1, col]),
+            Direction::Left => Some([row, col.checked_sub(1)?]),
+            Direction::Right => Some([row, col + 1]),
+        }
+    }
+
+    fn inverse(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    /// Letter used for the direction in move notation
+    fn letter(self) -> char {
+        match self {
+            Direction::Up => 'U',
+            Direction::Down => 'D',
+            Direction::Left => 'L',
+            Direction::Right => 'R',
+        }
+    }
+
+    fn from_letter(c: char) -> Option<Self> {
+        Direction::ALL
+            .into_iter()
+            .find(|dir| dir.letter() == c.to_ascii_uppercase())
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+        })
+    }
+}
+
+/// Squares a piece covers, relative to the top left corner of its bounding box, in [row, column] order
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Shape {
+    cells: Vec<[usize; 2]>,
+}
+
+impl Shape {
+    /// A solid rectangle `width` squares across and `height` squares down
+    pub fn rect(width: usize, height: usize) -> Self {
+        let cells = (0..height)
+            .flat_map(|row| (0..width).map(move |col| [row, col]))
+            .collect();
+        Self { cells }
+    }
+
+    /// Any set of squares, like an L-shaped polyomino. The squares are moved so the shape starts at `[0, 0]`.
+    pub fn from_cells(cells: &[[usize; 2]]) -> Self {
+        let top = cells.iter().map(|c| c[0]).min().unwrap_or(0);
+        let left = cells.iter().map(|c| c[1]).min().unwrap_or(0);
+        let mut cells: Vec<[usize; 2]> = cells
+            .iter()
+            .map(|[row, col]| [row - top, col - left])
+            .collect();
+        cells.sort();
+        cells.dedup();
+        Self { cells }
+    }
+
+    /// Squares covered by the shape when the top left corner of its bounding box is at `corner`
+    fn squares(&self, [row, col]: [usize; 2]) -> impl Iterator<Item = [usize; 2]> + '_ {
+        self.cells.iter().map(move |[r, c]| [row + r, col + c])
+    }
+}
+
+/// The pieces of the classic puzzle, for use with [`Game::from_layout`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Block {
+    /// 1x1 piece
     Small,
-    Blank,
+    /// Piece one square wide and two tall
+    Tall,
+    /// Piece two squares wide and one tall
     Wide,
+    /// 2x2 piece, usually the one that has to be moved to the goal
     Large,
 }
 
-#[derive(Copy, Clone, Debug)]
+impl From<Block> for Shape {
+    fn from(block: Block) -> Self {
+        match block {
+            Block::Small => Shape::rect(1, 1),
+            Block::Tall => Shape::rect(1, 2),
+            Block::Wide => Shape::rect(2, 1),
+            Block::Large => Shape::rect(2, 2),
+        }
+    }
+}
+
+/// Reasons a layout can't be turned into a Game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutError {
+    /// There are too many pieces on a board this size to pack into a search state
+    TooBig { rows: usize, cols: usize },
+    /// The piece at this index in the list covers no squares
+    EmptyPiece(usize),
+    /// The piece at this index in the list hangs off the edge of the board
+    OutOfBounds(usize),
+    /// More than one piece covers this square, in [row, column] order
+    Overlap([usize; 2]),
+    /// The pieces cover every square, so nothing can move
+    BlankCount(usize),
+    /// The target isn't the index of a piece in the list
+    UnknownTarget(usize),
+    /// The target piece wouldn't fit on the board at the goal
+    GoalOutOfBounds,
+    /// The blank squares a game has recorded aren't the ones its pieces leave uncovered
+    StaleBlanks,
+}
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutError::TooBig { rows, cols } => write!(
+                f,
+                "board is {}x{}, too big for the number of pieces on it",
+                rows, cols
+            ),
+            LayoutError::EmptyPiece(i) => write!(f, "piece {} covers no squares", i),
+            LayoutError::OutOfBounds(i) => write!(f, "piece {} does not fit on the board", i),
+            LayoutError::Overlap([row, col]) => {
+                write!(f, "more than one piece covers row {}, column {}", row, col)
+            }
+            LayoutError::BlankCount(n) => {
+                write!(f, "layout has {} blank squares, must have at least 1", n)
+            }
+            LayoutError::UnknownTarget(i) => write!(f, "target {} is not one of the pieces", i),
+            LayoutError::GoalOutOfBounds => {
+                f.write_str("target piece does not fit on the board at the goal")
+            }
+            LayoutError::StaleBlanks => {
+                f.write_str("recorded blank squares do not match the board")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// Slides the piece with the given id one square in each direction of `path` in turn.
+///
+/// `Display` writes the compact form that [`Move::parse_list`] reads. Naming the piece takes the game it belongs to,
+/// so moves are written in words by [`Game::show_move`] and [`Game::describe`].
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Move {
-    start_1: [usize; 2],
-    end_1: [usize; 2],
-    start_2: Option<[usize; 2]>,
-    end_2: Option<[usize; 2]>,
+    pub piece: usize,
+    pub path: Vec<Direction>,
+}
+
+/// Writes the move as the piece id followed by a letter for each direction, so `4DL` moves piece 4 down then left
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.piece)?;
+        for dir in &self.path {
+            write!(f, "{}", dir.letter())?;
+        }
+        Ok(())
+    }
+}
+
+/// A move written in words, see [`Game::show_move`]
+struct ShowMove<'a> {
+    game: &'a Game,
+    m: &'a Move,
+}
+
+impl Display for ShowMove<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.game.names().get(self.m.piece) {
+            Some(name) => f.write_str(name)?,
+            None => write!(f, "P{}", self.m.piece)?,
+        }
+        for (i, dir) in self.m.path.iter().enumerate() {
+            f.write_str(if i == 0 { " " } else { " then " })?;
+            write!(f, "{}", dir)?;
+        }
+        Ok(())
+    }
+}
+
+impl Move {
+    /// Reads a list of moves written like [`Game::print_moves`], separated by whitespace or commas
+    pub fn parse_list(s: &str) -> Result<Vec<Move>, ParsePieceMoveError> {
+        let mut moves = vec![];
+        let mut chars = s.char_indices().peekable();
+        while let Some(&(pos, c)) = chars.peek() {
+            if c == ',' || c.is_whitespace() {
+                chars.next();
+                continue;
+            }
+            let mut piece = String::new();
+            while let Some((_, d)) = chars.next_if(|(_, d)| d.is_ascii_digit()) {
+                piece.push(d);
+            }
+            let piece = piece
+                .parse()
+                .map_err(|_| ParsePieceMoveError::MissingPiece { pos })?;
+
+            let mut path = vec![];
+            while let Some(&(pos, c)) = chars.peek() {
+                if c == ',' || c.is_whitespace() {
+                    break;
+                }
+                let dir = Direction::from_letter(c)
+                    .ok_or(ParsePieceMoveError::UnknownDirection { pos, found: c })?;
+                path.push(dir);
+                chars.next();
+            }
+            if path.is_empty() {
+                return Err(ParsePieceMoveError::MissingDirection { pos });
+            }
+            moves.push(Move { piece, path });
+        }
+        Ok(moves)
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParsePieceMoveError;
+
+    /// Parses a single move like `4DL`, letters in either case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut moves = Move::parse_list(s)?;
+        match moves.len() {
+            0 => Err(ParsePieceMoveError::Empty),
+            1 => Ok(moves.pop().unwrap()),
+            // Point at the separator after the first move
+            _ => {
+                let separator = |c: char| c == ',' || c.is_whitespace();
+                let start = s.len() - s.trim_start_matches(separator).len();
+                let pos = start + s[start..].find(separator).unwrap();
+                Err(ParsePieceMoveError::UnknownDirection {
+                    pos,
+                    found: s[pos..].chars().next().unwrap(),
+                })
+            }
+        }
+    }
+}
+
+/// Error returned when a move written like `4DL` can't be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParsePieceMoveError {
+    /// There was nothing to parse
+    Empty,
+    /// Expected a piece id at `pos`
+    MissingPiece { pos: usize },
+    /// The move starting at `pos` has no directions after its piece id
+    MissingDirection { pos: usize },
+    /// The character at `pos` is not U, D, L or R
+    UnknownDirection { pos: usize, found: char },
+}
+
+impl Display for ParsePieceMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParsePieceMoveError::Empty => f.write_str("no move given"),
+            ParsePieceMoveError::MissingPiece { pos } => {
+                write!(f, "expected a piece id at position {}", pos)
+            }
+            ParsePieceMoveError::MissingDirection { pos } => {
+                write!(f, "move at position {} has no direction", pos)
+            }
+            ParsePieceMoveError::UnknownDirection { pos, found } => write!(
+                f,
+                "unexpected {:?} at position {}, expected U, D, L or R",
+                found, pos
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParsePieceMoveError {}
+
+/// Error returned when a move can't be made because the piece doesn't exist, it goes nowhere or something is in its
+/// way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockedMove(pub Move);
+
+impl Display for BlockedMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "move {} is blocked", self.0)
+    }
+}
+
+impl std::error::Error for BlockedMove {}
+
+/// Error returned when no sequence of moves brings the target piece to the goal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoSolution;
+
+impl Display for NoSolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("the target piece can't reach the goal")
+    }
+}
+
+impl std::error::Error for NoSolution {}
+
+/// How moves are counted, which decides what [`Game::list_moves`] offers and what the solver minimises
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Metric {
+    /// Moving a piece one square is one move
+    #[default]
+    Step,
+    /// Sliding one piece any distance, even around corners, is one move
+    Slide,
+}
+
+/// Move found by a search, where pieces with the same shape are interchangeable and so have no id. The piece
+/// covering `from` moves so that the same part of it covers `to`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Shift {
+    pub from: [usize; 2],
+    pub to: [usize; 2],
+}
+
+/// A piece's shape and the top left corner of its bounding box, as passed to [`Game::from_layout`]
+pub type Placement = (Shape, [usize; 2]);
+
+#[derive(Clone, Debug)]
+struct Piece {
+    shape: Shape,
+    /// Top left corner of the shape's bounding box, in [row, column] order
+    corner: [usize; 2],
+}
+
+/// Which piece covers each square, by index in `pieces`
+fn occupancy(pieces: &[Piece], rows: usize, cols: usize) -> Array2D<Option<usize>> {
+    let mut board = Array2D::filled_with(None, rows, cols);
+    for (id, piece) in pieces.iter().enumerate() {
+        for [row, col] in piece.shape.squares(piece.corner) {
+            board.set(row, col, Some(id)).unwrap();
+        }
+    }
+    board
+}
+
+/// Which piece covers each square like [`occupancy`], checking that every piece is on the board and that none of
+/// them overlap
+fn checked_occupancy(
+    pieces: &[Piece],
+    rows: usize,
+    cols: usize,
+) -> Result<Array2D<Option<usize>>, LayoutError> {
+    let mut board = Array2D::filled_with(None, rows, cols);
+    for (id, piece) in pieces.iter().enumerate() {
+        if piece.shape.cells.is_empty() {
+            return Err(LayoutError::EmptyPiece(id));
+        }
+        for [row, col] in piece.shape.squares(piece.corner) {
+            match board.get(row, col) {
+                None => return Err(LayoutError::OutOfBounds(id)),
+                Some(None) => board.set(row, col, Some(id)).unwrap(),
+                Some(Some(_)) => return Err(LayoutError::Overlap([row, col])),
+            }
+        }
+    }
+    Ok(board)
+}
+
+/// Checks if the piece with the given id would be on the board and clear of every other piece with its corner here
+fn fits(occupancy: &Array2D<Option<usize>>, shape: &Shape, id: usize, corner: [usize; 2]) -> bool {
+    shape
+        .squares(corner)
+        .all(|[row, col]| match occupancy.get(row, col) {
+            Some(Some(other)) => *other == id,
+            Some(None) => true,
+            None => false,
+        })
+}
+
+/// Where the piece with the given id would move to, if the squares it would cover are on the board and free
+fn slide(
+    occupancy: &Array2D<Option<usize>>,
+    pieces: &[Piece],
+    id: usize,
+    dir: Direction,
+) -> Option<[usize; 2]> {
+    let piece = &pieces[id];
+    let corner = dir.step(piece.corner)?;
+    fits(occupancy, &piece.shape, id, corner).then_some(corner)
+}
+
+/// Corner a piece can reach, and the index of the entry it was reached from along with the direction taken
+type Reached = ([usize; 2], Option<(usize, Direction)>);
+
+/// Every corner the piece with the given id can reach in one move under `metric` while the others stay put, found
+/// breadth first so that each path is as short as possible. The first entry is where the piece already is.
+fn reachable(
+    occupancy: &Array2D<Option<usize>>,
+    piece: &Piece,
+    id: usize,
+    metric: Metric,
+) -> Vec<Reached> {
+    let mut reached = vec![(piece.corner, None)];
+    let mut i = 0;
+    // A step only goes one square, from where the piece already is
+    while i < reached.len() && (metric == Metric::Slide || i == 0) {
+        for dir in Direction::ALL {
+            let Some(corner) = dir.step(reached[i].0) else {
+                continue;
+            };
+            if fits(occupancy, &piece.shape, id, corner)
+                && reached.iter().all(|(c, _)| *c != corner)
+            {
+                reached.push((corner, Some((i, dir))));
+            }
+        }
+        i += 1;
+    }
+    reached
+}
+
+/// Directions that lead to entry `i` of a list returned by [`reachable`]
+fn path(reached: &[Reached], mut i: usize) -> Vec<Direction> {
+    let mut path = vec![];
+    while let Some((parent, dir)) = reached[i].1 {
+        path.push(dir);
+        i = parent;
+    }
+    path.reverse();
+    path
+}
+
+/// Pieces next to a blank square. Only these can move, since every move covers at least one blank square.
+fn candidates(occupancy: &Array2D<Option<usize>>, blanks: &[[usize; 2]]) -> Vec<usize> {
+    let mut candidates = vec![];
+    for blank in blanks {
+        for dir in Direction::ALL {
+            let Some([row, col]) = dir.step(*blank) else {
+                continue;
+            };
+            if let Some(Some(id)) = occupancy.get(row, col) {
+                candidates.push(*id);
+            }
+        }
+    }
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+/// Squares not covered by any piece, in reading order
+fn blanks(occupancy: &Array2D<Option<usize>>) -> Vec<[usize; 2]> {
+    let cols = occupancy.num_columns();
+    occupancy
+        .elements_row_major_iter()
+        .enumerate()
+        .filter(|(_, id)| id.is_none())
+        .map(|(i, _)| [i / cols, i % cols])
+        .collect()
 }
 
 #[derive(Clone, Debug)]
 pub struct Game {
-    /// 2D array of Piece objects representing the board
-    board: Array2D<Piece>,
-    /// Positions of the blank squares in the board, stored as [row, column] order
-    blank_pos: ([usize; 2], [usize; 2]),
+    rows: usize,
+    cols: usize,
+    /// Every piece on the board, indexed by id
+    pieces: Vec<Piece>,
+    /// Squares not covered by any piece, in reading order
+    blanks: Vec<[usize; 2]>,
+    /// Id of the piece that has to reach the goal
+    target: usize,
+    /// Position the top left corner of the target piece has to reach, in [row, column] order
+    goal: [usize; 2],
+    /// Piece ids ordered so that interchangeable pieces are next to each other, which is the order their corners
+    /// are packed into a search state in
+    order: Vec<usize>,
+    /// Ranges of `order` holding interchangeable pieces
+    groups: Vec<std::ops::Range<usize>>,
+    /// Bits used for each corner in a packed search state
+    bits: usize,
     /// Vector of moves made to get to this position
-    pub moves: Vec<Move>
+    pub moves: Vec<Move>,
+    /// How moves are counted when listing and solving
+    pub metric: Metric,
+    /// Whether to run [`Game::verify`] after every square a piece moves, panicking if it fails
+    pub check_invariants: bool,
 }
 
 impl std::fmt::Display for Game {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut s = String::new();
-        for row in self.board.rows_iter() {
-            s += "|";
-            for piece in row {
-                match piece {
-                    Piece::Tall(i) => s += &format!("T{}|", i),
-                    Piece::Small => s += "SS|",
-                    Piece::Blank => s += "  |",
-                    Piece::Wide => s += "WW|",
-                    Piece::Large => s += "LL|",
-                }
-            }
-            s += "\n";
+        f.write_str(&self.render(None))
+    }
+}
+
+/// Iterator over every position a game went through, returned by [`Game::replay`]
+pub struct Replay<'a> {
+    next: Option<Game>,
+    moves: std::slice::Iter<'a, Move>,
+}
+
+impl Iterator for Replay<'_> {
+    type Item = Game;
+
+    fn next(&mut self) -> Option<Game> {
+        let current = self.next.take()?;
+        if let Some(m) = self.moves.next() {
+            let mut next = current.clone();
+            next.make_move(m.clone());
+            self.next = Some(next);
         }
+        Some(current)
+    }
+}
 
-        f.write_str(&s)
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Game {
     /// Returns a new Game with the default block positions
     pub fn new() -> Self {
-        let board = Array2D::from_rows(&vec![vec![Piece::Tall(0), Piece::Large, Piece::Large, Piece::Tall(1)], vec![Piece::Tall(0), Piece::Large, Piece::Large, Piece::Tall(1)], vec![Piece::Blank, Piece::Wide, Piece::Wide, Piece::Blank], vec![Piece::Tall(2), Piece::Small, Piece::Small, Piece::Tall(3)], vec![Piece::Tall(2), Piece::Small, Piece::Small, Piece::Tall(3)]]).unwrap();
-        let blank_pos = ([2, 0], [2, 3]);
-        Self {board, blank_pos, moves: vec![]}
+        Self::classic(&[
+            (Block::Tall, [0, 0]),
+            (Block::Large, [0, 1]),
+            (Block::Tall, [0, 3]),
+            (Block::Wide, [2, 1]),
+            (Block::Tall, [3, 0]),
+            (Block::Small, [3, 1]),
+            (Block::Small, [3, 2]),
+            (Block::Tall, [3, 3]),
+            (Block::Small, [4, 1]),
+            (Block::Small, [4, 2]),
+        ])
+    }
+
+    /// Returns the classic Huarong Dao layout, where the large piece starts hemmed in by four tall pieces
+    pub fn huarong_dao() -> Self {
+        Self::classic(&[
+            (Block::Tall, [0, 0]),
+            (Block::Large, [0, 1]),
+            (Block::Tall, [0, 3]),
+            (Block::Tall, [2, 0]),
+            (Block::Wide, [2, 1]),
+            (Block::Tall, [2, 3]),
+            (Block::Small, [3, 1]),
+            (Block::Small, [3, 2]),
+            (Block::Small, [4, 0]),
+            (Block::Small, [4, 3]),
+        ])
+    }
+
+    /// 4x5 board where the large piece has to reach the bottom middle
+    fn classic(blocks: &[(Block, [usize; 2])]) -> Self {
+        let pieces: Vec<Placement> = blocks
+            .iter()
+            .map(|(block, corner)| ((*block).into(), *corner))
+            .collect();
+        let target = blocks
+            .iter()
+            .position(|(block, _)| *block == Block::Large)
+            .unwrap();
+        Self::from_layout(5, 4, &pieces, target, [3, 1]).unwrap()
+    }
+
+    /// Builds a Game from a list of pieces and the top left corner of each one's bounding box, in [row, column] order.
+    /// Each piece's id is its index in the list.
+    ///
+    /// Squares not covered by a piece are blank, and there has to be at least one of them. The game is solved when
+    /// the top left corner of the piece with id `target` reaches `goal`.
+    pub fn from_layout(
+        rows: usize,
+        cols: usize,
+        pieces: &[Placement],
+        target: usize,
+        goal: [usize; 2],
+    ) -> Result<Self, LayoutError> {
+        // Enough bits to store the index of any square
+        let bits = (usize::BITS - (rows * cols).saturating_sub(1).leading_zeros()).max(1) as usize;
+        if pieces.len() * bits > 128 {
+            return Err(LayoutError::TooBig { rows, cols });
+        }
+        let placed: Vec<Piece> = pieces
+            .iter()
+            .map(|(shape, corner)| Piece {
+                shape: shape.clone(),
+                corner: *corner,
+            })
+            .collect();
+        let blanks = blanks(&checked_occupancy(&placed, rows, cols)?);
+        if blanks.is_empty() {
+            return Err(LayoutError::BlankCount(0));
+        }
+        let Some((target_shape, _)) = pieces.get(target) else {
+            return Err(LayoutError::UnknownTarget(target));
+        };
+        if target_shape
+            .squares(goal)
+            .any(|[row, col]| row >= rows || col >= cols)
+        {
+            return Err(LayoutError::GoalOutOfBounds);
+        }
+
+        // Pieces with the same shape can be swapped without changing the puzzle, unless one of them is the target
+        let class = |id: usize| (id == target, &pieces[id].0);
+        let mut order: Vec<usize> = (0..pieces.len()).collect();
+        order.sort_by_key(|id| class(*id));
+        let mut groups = vec![];
+        let mut start = 0;
+        for end in 1..=order.len() {
+            if end == order.len() || class(order[end]) != class(order[start]) {
+                groups.push(start..end);
+                start = end;
+            }
+        }
+
+        Ok(Self {
+            rows,
+            cols,
+            pieces: placed,
+            blanks,
+            target,
+            goal,
+            order,
+            groups,
+            bits,
+            moves: vec![],
+            metric: Metric::default(),
+            check_invariants: false,
+        })
+    }
+
+    /// Id of the piece covering a square, if any
+    pub fn piece_at(&self, [row, col]: [usize; 2]) -> Option<usize> {
+        *occupancy(&self.pieces, self.rows, self.cols).get(row, col)?
+    }
+
+    /// Squares not covered by any piece, in reading order
+    pub fn blanks(&self) -> &[[usize; 2]] {
+        &self.blanks
     }
 
-    /// Returns a list of all possible moves from the given position
+    /// Returns a list of all possible moves from the given position under the game's metric. With
+    /// [`Metric::Slide`] there is one move for every square a piece can reach, along the shortest path there.
     pub fn list_moves(&self) -> Vec<Move> {
+        let occupancy = occupancy(&self.pieces, self.rows, self.cols);
         let mut moves = vec![];
-        let (start_1, start_2) = self.blank_pos;
-        let ([b1_row, b1_col], [b2_row, b2_col]) = self.blank_pos;
-        let right = self.board.get(b1_row, b1_col + 1);
-        match right {
-            Some(Piece::Small) => moves.push(Move {start_1, end_1: [b1_row, b1_col + 1], start_2: None, end_2: None}),
-            Some(Piece::Wide) => moves.push(Move {start_1, end_1: [b1_row, b1_col + 2], start_2: None, end_2: None}),
-            Some(Piece::Large) => {
-                if self.board.get(b2_row, b2_col + 1) == Some(&Piece::Large) {
-                    moves.push(Move {start_1, end_1: [b1_row, b1_col + 2], start_2: Some(start_2), end_2: Some([b2_row, b2_col + 2])});
+        for piece in candidates(&occupancy, &self.blanks) {
+            let reached = reachable(&occupancy, &self.pieces[piece], piece, self.metric);
+            for i in 1..reached.len() {
+                moves.push(Move {
+                    piece,
+                    path: path(&reached, i),
+                });
+            }
+        }
+        moves
+    }
+
+    /// Slides a piece along the move's path, panicking if it is blocked
+    pub fn make_move(&mut self, m: Move) {
+        if let Err(e) = self.try_move(m) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Slides a piece along the move's path, leaving the game as it was if the piece doesn't exist, the path is
+    /// empty or the piece would be blocked at any point
+    pub fn try_move(&mut self, m: Move) -> Result<(), BlockedMove> {
+        let Some(piece) = self.pieces.get(m.piece) else {
+            return Err(BlockedMove(m));
+        };
+        // A move without directions couldn't be written down and read back
+        if m.path.is_empty() {
+            return Err(BlockedMove(m));
+        }
+        let occupancy = occupancy(&self.pieces, self.rows, self.cols);
+        let mut corner = piece.corner;
+        for dir in &m.path {
+            match dir.step(corner) {
+                Some(next) if fits(&occupancy, &piece.shape, m.piece, next) => corner = next,
+                _ => return Err(BlockedMove(m)),
+            }
+        }
+        for dir in &m.path {
+            self.shift(m.piece, *dir);
+        }
+        self.moves.push(m);
+        Ok(())
+    }
+
+    /// Makes every move in order, stopping at the first one that is blocked and leaving the moves before it made
+    pub fn apply_moves(&mut self, moves: &[Move]) -> Result<(), BlockedMove> {
+        for m in moves {
+            self.try_move(m.clone())?;
+        }
+        Ok(())
+    }
+
+    /// The moves made so far, written like `4DL 2U` so they can be read back with [`Move::parse_list`]
+    pub fn print_moves(&self) -> String {
+        let moves: Vec<String> = self.moves.iter().map(Move::to_string).collect();
+        moves.join(" ")
+    }
+
+    /// Describes a move in words, like `Large down` or `T2 up`
+    pub fn describe(&self, m: &Move) -> String {
+        self.show_move(m).to_string()
+    }
+
+    /// Displays a move in words like [`Game::describe`], without building the string up front
+    pub fn show_move<'a>(&'a self, m: &'a Move) -> impl Display + 'a {
+        ShowMove { game: self, m }
+    }
+
+    /// Name of each piece in descriptions of moves, like `Large`, `Small 2` or `T0`, with a number added when more
+    /// than one piece would otherwise share it
+    fn names(&self) -> Vec<String> {
+        let names: Vec<String> = self
+            .labels()
+            .into_iter()
+            .map(|label| match label.as_str() {
+                "LL" => "Large".to_string(),
+                "SS" => "Small".to_string(),
+                "WW" => "Wide".to_string(),
+                _ => label,
+            })
+            .collect();
+        names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| {
+                if names.iter().filter(|n| *n == name).count() == 1 {
+                    return name.clone();
                 }
-            },
-            Some(Piece::Tall(i)) => {
-                if let Some(Piece::Tall(i_2)) =  self.board.get(b2_row, b2_col + 1) {
-                    if *i == *i_2 {
-                        moves.push(Move {start_1, end_1: [b1_row, b1_col + 1], start_2: Some(start_2), end_2: Some([b2_row, b2_col + 1])});
-                    }
+                let rank = names[..id].iter().filter(|n| *n == name).count();
+                format!("{} {}", name, rank)
+            })
+            .collect()
+    }
+
+    /// Scrambles the game by making `n_moves` random moves under its metric, never sending a piece straight back to
+    /// where it was before the previous move unless nothing else can move. The moves aren't recorded. The same each
+    /// time for a given seed.
+    pub fn random_walk(&mut self, n_moves: usize, seed: u64) {
+        let mut rng = Rng::new(seed);
+        // The piece moved last and where its corner was before that
+        let mut back: Option<(usize, [usize; 2])> = None;
+        for _ in 0..n_moves {
+            let mut moves = self.list_moves();
+            if let Some((piece, corner)) = back {
+                let retreats = |m: &Move| m.piece == piece && self.destination(m) == corner;
+                if moves.iter().any(|m| !retreats(m)) {
+                    moves.retain(|m| !retreats(m));
                 }
             }
-            _ => {}
+            if moves.is_empty() {
+                break;
+            }
+            let m = moves.swap_remove(rng.below(moves.len()));
+            back = Some((m.piece, self.pieces[m.piece].corner));
+            for dir in &m.path {
+                self.shift(m.piece, *dir);
+            }
         }
-        let left = self.board.get(b1_row, b1_col.wrapping_sub(1));
-        match left {
-            Some(Piece::Small) => moves.push(Move {start_1, end_1: [b1_row, b1_col - 1], start_2: None, end_2: None}),
-            Some(Piece::Wide) => moves.push(Move {start_1, end_1: [b1_row, b1_col - 2], start_2: None, end_2: None}),
-            Some(Piece::Large) => {
-                if self.board.get(b2_row, b2_col.wrapping_sub(1)) == Some(&Piece::Large) {
-                    moves.push(Move {start_1, end_1: [b1_row, b1_col - 2], start_2: Some(start_2), end_2: Some([b2_row, b2_col - 2])});
-                }
-            },
-            Some(Piece::Tall(i)) => {
-                if let Some(Piece::Tall(i_2)) = self.board.get(b2_row, b2_col.wrapping_sub(1)) {
-                    if *i == *i_2 {
-                        moves.push(Move {start_1, end_1: [b1_row, b1_col.wrapping_sub(1)], start_2: Some(start_2), end_2: Some([b2_row, b2_col - 1])});
+    }
+
+    /// Where the corner of the moved piece ends up after a move that is known to be possible
+    fn destination(&self, m: &Move) -> [usize; 2] {
+        m.path
+            .iter()
+            .fold(self.pieces[m.piece].corner, |corner, dir| {
+                dir.step(corner).unwrap()
+            })
+    }
+
+    /// Takes back the last move, returning it, or `None` if no moves have been made
+    pub fn undo(&mut self) -> Option<Move> {
+        let m = self.moves.pop()?;
+        for dir in m.path.iter().rev() {
+            self.shift(m.piece, dir.inverse());
+        }
+        Some(m)
+    }
+
+    /// Every position from the start of the game to now, with the moves made to reach each one. The first has no
+    /// moves, and the piece that moved to reach any of the others is the one in its last move.
+    pub fn replay(&self) -> Replay<'_> {
+        let mut start = self.clone();
+        while start.undo().is_some() {}
+        Replay {
+            next: Some(start),
+            moves: self.moves.iter(),
+        }
+    }
+
+    /// The board as shown by `Display`, with the squares of the given piece in inverse video so it stands out in a
+    /// terminal
+    pub fn highlighted(&self, piece: usize) -> String {
+        self.render(Some(piece))
+    }
+
+    fn render(&self, highlight: Option<usize>) -> String {
+        let labels = self.labels();
+        let mut s = String::new();
+        for row in occupancy(&self.pieces, self.rows, self.cols).rows_iter() {
+            s += "|";
+            for id in row {
+                match id {
+                    Some(id) if Some(*id) == highlight => {
+                        s += &format!("\x1b[7m{}\x1b[0m|", labels[*id])
                     }
+                    Some(id) => s += &format!("{}|", labels[*id]),
+                    None => s += "  |",
                 }
             }
-            _ => {}
+            s += "\n";
         }
-        let up = self.board.get(b1_row.wrapping_sub(1), b1_col);
-        match up {
-            Some(Piece::Small) => moves.push(Move {start_1, end_1: [b1_row - 1, b1_col], start_2: None, end_2: None}),
-            Some(Piece::Tall(_)) => moves.push(Move {start_1, end_1: [b1_row - 2, b1_col], start_2: None, end_2: None}),
-            Some(Piece::Large) => {
-                if self.board.get(b2_row.wrapping_sub(1), b2_col) == Some(&Piece::Large) {
-                    moves.push(Move {start_1, end_1: [b1_row - 2, b1_col], start_2: Some(start_2), end_2: Some([b2_row - 2, b2_col])});
-                }
-            },
-            Some(Piece::Wide) => {
-                if self.board.get(b2_row.wrapping_sub(1), b2_col) == Some(&Piece::Wide) {
-                    moves.push(Move {start_1, end_1: [b1_row - 1, b1_col], start_2: Some(start_2), end_2: Some([b2_row - 1, b2_col])});
-                }
-            },
-            _ => {}
-        }
-        let down = self.board.get(b1_row + 1, b1_col);
-        match down {
-            Some(Piece::Small) => moves.push(Move {start_1, end_1: [b1_row + 1, b1_col], start_2: None, end_2: None}),
-            Some(Piece::Tall(_)) => moves.push(Move {start_1, end_1: [b1_row + 2, b1_col], start_2: None, end_2: None}),
-            Some(Piece::Large) => {
-                if self.board.get(b2_row + 1, b2_col) == Some(&Piece::Large) {
-                    moves.push(Move {start_1, end_1: [b1_row + 2, b1_col], start_2: Some(start_2), end_2: Some([b2_row + 2, b2_col])});
-                }
-            },
-            Some(Piece::Wide) => {
-                if self.board.get(b2_row + 1, b2_col) == Some(&Piece::Wide) {
-                    moves.push(Move {start_1, end_1: [b1_row + 1, b1_col], start_2: Some(start_2), end_2: Some([b2_row + 1, b2_col])});
-                }
-            },
-            _ => {}
+        s
+    }
+
+    /// Moves a piece one square without recording it, panicking if it is blocked
+    fn shift(&mut self, id: usize, dir: Direction) {
+        let before = occupancy(&self.pieces, self.rows, self.cols);
+        let corner = slide(&before, &self.pieces, id, dir).expect("piece is blocked");
+        self.pieces[id].corner = corner;
+        // Worked out again from scratch rather than patched, so it can't drift from where the pieces are
+        self.blanks = blanks(&occupancy(&self.pieces, self.rows, self.cols));
+        if self.check_invariants {
+            if let Err(e) = self.verify() {
+                panic!("moving piece {} {:?} broke the board: {}", id, dir, e);
+            }
         }
+    }
 
-        let b2_right = self.board.get(b2_row, b2_col + 1);
-        match b2_right {
-            Some(Piece::Small) => moves.push(Move {start_1: start_2, end_1: [b2_row, b2_col + 1], start_2: None, end_2: None}),
-            Some(Piece::Wide) => moves.push(Move {start_1: start_2, end_1: [b2_row, b2_col + 2], start_2: None, end_2: None}),
-            _ => {}
+    /// Checks that every piece is on the board, that none of them overlap, and that the recorded blank squares are
+    /// the ones left uncovered
+    pub fn verify(&self) -> Result<(), LayoutError> {
+        let blanks = blanks(&checked_occupancy(&self.pieces, self.rows, self.cols)?);
+        if blanks.is_empty() {
+            return Err(LayoutError::BlankCount(0));
         }
+        if blanks != self.blanks {
+            return Err(LayoutError::StaleBlanks);
+        }
+        Ok(())
+    }
+
+    /// Finds the shortest solution under the game's metric with an A* search and plays it. Leaves the game
+    /// untouched if the target piece can't reach the goal.
+    pub fn solve(&mut self) -> Result<(), NoSolution> {
+        self.solve_monitored(&mut Monitor::new())
+    }
 
-        let b2_left = self.board.get(b2_row, b2_col.wrapping_sub(1));
-        match b2_left {
-            Some(Piece::Small) => moves.push(Move {start_1: start_2, end_1: [b2_row, b2_col - 1], start_2: None, end_2: None}),
-            Some(Piece::Wide) => moves.push(Move {start_1: start_2, end_1: [b2_row, b2_col - 2], start_2: None, end_2: None}),
-            _ => {}
+    /// [`Game::solve`], recording the search in `monitor`
+    pub fn solve_monitored(&mut self, monitor: &mut Monitor) -> Result<(), NoSolution> {
+        let steps = search::a_star_monitored(self, monitor).ok_or(NoSolution)?;
+        for step in steps {
+            self.apply(step);
         }
+        Ok(())
+    }
+
+    /// Text shown for each piece by `Display`. Pieces of the classic puzzle use their usual letters, with the tall
+    /// ones numbered in order, and anything else is shown as `P` and its id.
+    fn labels(&self) -> Vec<String> {
+        let mut talls = 0;
+        self.pieces
+            .iter()
+            .enumerate()
+            .map(|(id, piece)| {
+                let is = |block| piece.shape == Shape::from(block);
+                match id == self.target {
+                    true if is(Block::Large) => "LL".to_string(),
+                    false if is(Block::Small) => "SS".to_string(),
+                    false if is(Block::Wide) => "WW".to_string(),
+                    false if is(Block::Tall) => {
+                        talls += 1;
+                        format!("T{}", talls - 1)
+                    }
+                    _ => format!("P{}", id),
+                }
+            })
+            .collect()
+    }
+
+    /// Slot in `order` of the target piece, which is always in a group of its own
+    fn target_slot(&self) -> usize {
+        self.order.iter().position(|id| *id == self.target).unwrap()
+    }
+
+    /// Splits a packed state into the corner of the piece in each slot of `order`
+    fn unpack(&self, state: u128) -> Vec<[usize; 2]> {
+        (0..self.order.len())
+            .map(|slot| {
+                let index = (state >> (slot * self.bits)) as usize & ((1 << self.bits) - 1);
+                [index / self.cols, index % self.cols]
+            })
+            .collect()
+    }
 
-        let b2_up = self.board.get(b2_row.wrapping_sub(1), b2_col);
-        match b2_up {
-            Some(Piece::Small) => moves.push(Move {start_1: start_2, end_1: [b2_row - 1, b2_col], start_2: None, end_2: None}),
-            Some(Piece::Tall(_)) => moves.push(Move {start_1: start_2, end_1: [b2_row - 2, b2_col], start_2: None, end_2: None}),
-            _ => {}
+    /// Packs the corner of the piece in each slot of `order`, sorting interchangeable pieces so that every way of
+    /// arranging them gives the same state
+    fn pack(&self, corners: &mut [[usize; 2]]) -> u128 {
+        for group in &self.groups {
+            corners[group.clone()].sort();
         }
+        corners
+            .iter()
+            .enumerate()
+            .fold(0, |state, (slot, [row, col])| {
+                state | ((row * self.cols + col) as u128) << (slot * self.bits)
+            })
+    }
+}
+
+impl Puzzle for Game {
+    /// Corner of every piece as a row-major square index, a few bits each, with interchangeable pieces sorted so that
+    /// swapping them gives the same state
+    type State = u128;
+    type Move = Shift;
+
+    fn state(&self) -> u128 {
+        let mut corners: Vec<[usize; 2]> = self
+            .order
+            .iter()
+            .map(|id| self.pieces[*id].corner)
+            .collect();
+        self.pack(&mut corners)
+    }
 
-        let b2_down = self.board.get(b2_row + 1, b2_col);
-        match b2_down {
-            Some(Piece::Small) => moves.push(Move {start_1: start_2, end_1: [b2_row + 1, b2_col], start_2: None, end_2: None}),
-            Some(Piece::Tall(_)) => moves.push(Move {start_1: start_2, end_1: [b2_row + 2, b2_col], start_2: None, end_2: None}),
-            _ => {}
+    fn successors(&self, state: &u128) -> Vec<(Shift, u128)> {
+        let corners = self.unpack(*state);
+        // Lay the pieces out by slot rather than by id, since the state doesn't say which of a group is which
+        let pieces: Vec<Piece> = self
+            .order
+            .iter()
+            .zip(&corners)
+            .map(|(id, corner)| Piece {
+                shape: self.pieces[*id].shape.clone(),
+                corner: *corner,
+            })
+            .collect();
+        let occupancy = occupancy(&pieces, self.rows, self.cols);
+
+        let mut successors = vec![];
+        for slot in candidates(&occupancy, &blanks(&occupancy)) {
+            let piece = &pieces[slot];
+            let from = piece.shape.squares(piece.corner).next().unwrap();
+            for (corner, _) in reachable(&occupancy, piece, slot, self.metric)
+                .into_iter()
+                .skip(1)
+            {
+                let to = piece.shape.squares(corner).next().unwrap();
+                let mut next = corners.clone();
+                next[slot] = corner;
+                successors.push((Shift { from, to }, self.pack(&mut next)));
+            }
         }
-        moves
+        successors
     }
 
-    /// Executes a Move by swapping the Pieces at the start and end positions
-    pub fn make_move(&mut self, m: Move) {
-        let start = m.start_1;
-        let end = m.end_1;
-        let _  = self.board.set(start[0], start[1], *self.board.get(end[0], end[1]).unwrap());
-        let _ = self.board.set(end[0], end[1], Piece::Blank);
-        if self.blank_pos.0 == start {
-            self.blank_pos.0 = end;
-        } else if self.blank_pos.1 == start {
-            self.blank_pos.1 = end;
-        }
-        if let (Some(start), Some(end)) = (m.start_2, m.end_2) {
-            let _ = self.board.set(start[0], start[1], *self.board.get(end[0], end[1]).unwrap());
-            let _ = self.board.set(end[0], end[1], Piece::Blank);
-            self.blank_pos.1 = end;
+    /// Checks if the target piece is at the goal
+    fn is_goal(&self, state: &u128) -> bool {
+        self.unpack(*state)[self.target_slot()] == self.goal
+    }
+
+    /// Distance of the target piece from the goal, as every step shifts it by at most one square. A slide can take
+    /// it any distance, so then all that is known is that one more move is needed if it isn't there yet.
+    fn heuristic(&self, state: &u128) -> u32 {
+        let [row, col] = self.unpack(*state)[self.target_slot()];
+        let distance = (row.abs_diff(self.goal[0]) + col.abs_diff(self.goal[1])) as u32;
+        match self.metric {
+            Metric::Step => distance,
+            Metric::Slide => distance.min(1),
         }
-        self.moves.push(m);
     }
 
-    /// Checks if the large piece is in the solved position
-    fn check_solved(&self) -> bool {
-        if let (Some(a), Some(b), Some(c), Some(d)) = (self.board.get(3, 1), self.board.get(3, 2), self.board.get(4, 1), self.board.get(4, 2)) {
-            return *a == Piece::Large && *b == Piece::Large && *c == Piece::Large && *d == Piece::Large;
-        }
-        false
-    }
-
-    /// Performs a brute-force a* solve with pruning using a hash of the board's layout
-    pub fn solve(&mut self) {
-        let mut a_star = VecDeque::new();
-        let mut found_states = HashMap::<Array2D<Piece>, bool>::new();
-        found_states.insert(self.board.clone(), true);
-        a_star.push_back(self.clone());
-        loop {
-            let start = a_star.pop_front().unwrap();
-            for m in start.list_moves() {
-                let mut moved = start.clone();
-                moved.make_move(m);
-                if let Some(_) = found_states.insert(moved.board.clone(), true) {
-                } else if moved.check_solved() {
-                    *self = moved;
-                    return;
-                } else {
-                    a_star.push_back(moved);
+    fn apply(&mut self, shift: Shift) {
+        let id = self.piece_at(shift.from).expect("no piece to move");
+        let piece = &self.pieces[id];
+        let corner = [0, 1].map(|i| shift.to[i] + piece.corner[i] - shift.from[i]);
+        let occupancy = occupancy(&self.pieces, self.rows, self.cols);
+        let reached = reachable(&occupancy, piece, id, Metric::Slide);
+        let i = reached
+            .iter()
+            .position(|(c, _)| *c == corner)
+            .expect("piece can't reach the square");
+        self.make_move(Move {
+            piece: id,
+            path: path(&reached, i),
+        });
+    }
+}
+
+/// Error returned when a layout printed by Game's `Display` can't be read back. Lines and columns count from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseGameError {
+    /// There are no rows
+    Empty,
+    /// Expected a `|` at this position
+    MissingSeparator { line: usize, column: usize },
+    /// The square starting here isn't `  `, `SS`, `WW`, `LL`, or `T` or `P` followed by a number
+    UnknownPiece { line: usize, column: usize },
+    /// The row on this line has a different number of squares to the first row
+    RowLength {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The piece covering the square starting here isn't the right shape
+    BadShape { line: usize, column: usize },
+    /// There are this many large pieces, but there has to be exactly one to be the target
+    LargeCount(usize),
+    /// There is more text on this line, after the blank line that ended the layout
+    TrailingText { line: usize },
+    /// The pieces were read but don't make a valid game
+    Layout(LayoutError),
+}
+
+impl Display for ParseGameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseGameError::Empty => f.write_str("layout has no rows"),
+            ParseGameError::MissingSeparator { line, column } => {
+                write!(f, "line {}, column {}: expected '|'", line, column)
+            }
+            ParseGameError::UnknownPiece { line, column } => write!(
+                f,
+                "line {}, column {}: unknown piece, expected '  ', SS, WW, LL, or T or P and a number",
+                line, column
+            ),
+            ParseGameError::RowLength {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: row has {} squares, expected {}",
+                line, found, expected
+            ),
+            ParseGameError::BadShape { line, column } => {
+                write!(f, "line {}, column {}: piece is the wrong shape", line, column)
+            }
+            ParseGameError::LargeCount(n) => {
+                write!(f, "layout has {} large pieces, must have exactly 1", n)
+            }
+            ParseGameError::TrailingText { line } => {
+                write!(f, "line {}: unexpected text after the layout", line)
+            }
+            ParseGameError::Layout(e) => write!(f, "invalid layout: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ParseGameError {}
+
+/// What a square of a printed layout says about the piece covering it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Label {
+    Blank,
+    Small,
+    Wide,
+    Large,
+    /// Tall and other numbered pieces have the same number on all of their squares
+    Tall(usize),
+    Numbered(usize),
+}
+
+impl FromStr for Game {
+    type Err = ParseGameError;
+
+    /// Reads a layout in the format printed by `Display`, like `|T0|LL|LL|T1|` for each row.
+    ///
+    /// The large piece is the target, and has to reach the bottom middle of the board like in the default layout.
+    /// Tall pieces are given ids in the order of their numbers, and the rest in the order they are read. Blank lines
+    /// may come before and after the layout, but nothing else may follow it.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Each square with the line and column its text starts at
+        let mut grid: Vec<Vec<(Label, [usize; 2])>> = vec![];
+        let lines = s
+            .lines()
+            .enumerate()
+            .skip_while(|(_, l)| l.trim().is_empty());
+        let mut ended = false;
+        for (i, text) in lines {
+            let line = i + 1;
+            if text.trim().is_empty() {
+                ended = true;
+                continue;
+            }
+            if ended {
+                return Err(ParseGameError::TrailingText { line });
+            }
+            let start = text.len() - text.trim_start().len();
+            let text = text.trim();
+            if !text.starts_with('|') {
+                return Err(ParseGameError::MissingSeparator {
+                    line,
+                    column: start + 1,
+                });
+            }
+            let mut row = vec![];
+            let mut column = start + 2;
+            for square in text[1..].split_inclusive('|') {
+                let Some(square) = square.strip_suffix('|') else {
+                    return Err(ParseGameError::MissingSeparator {
+                        line,
+                        column: column + square.len(),
+                    });
+                };
+                let number = |prefix| square.strip_prefix(prefix).and_then(|n| n.parse().ok());
+                let label = match square {
+                    "  " => Label::Blank,
+                    "SS" => Label::Small,
+                    "WW" => Label::Wide,
+                    "LL" => Label::Large,
+                    _ => match (number('T'), number('P')) {
+                        (Some(i), _) => Label::Tall(i),
+                        (_, Some(i)) => Label::Numbered(i),
+                        _ => return Err(ParseGameError::UnknownPiece { line, column }),
+                    },
+                };
+                row.push((label, [line, column]));
+                column += square.len() + 1;
+            }
+            if let Some(first) = grid.first() {
+                if first.len() != row.len() {
+                    return Err(ParseGameError::RowLength {
+                        line,
+                        expected: first.len(),
+                        found: row.len(),
+                    });
                 }
             }
+            grid.push(row);
+        }
+        if grid.is_empty() {
+            return Err(ParseGameError::Empty);
         }
+
+        // Group the squares into pieces, each found from its first square in reading order
+        let (rows, cols) = (grid.len(), grid[0].len());
+        let at = |row: usize, col: usize| {
+            grid.get(row)
+                .and_then(|r| r.get(col))
+                .map(|(label, _)| *label)
+        };
+        let mut claimed = Array2D::filled_with(false, rows, cols);
+        // Each piece along with its number if it is a tall piece
+        let mut pieces: Vec<(Placement, Option<usize>)> = vec![];
+        let mut larges = vec![];
+        for row in 0..rows {
+            for col in 0..cols {
+                let (label, [line, column]) = grid[row][col];
+                if claimed[(row, col)] || label == Label::Blank {
+                    continue;
+                }
+                let (shape, corner) = match label {
+                    Label::Tall(_) | Label::Numbered(_) => {
+                        let squares: Vec<[usize; 2]> = (0..rows * cols)
+                            .map(|i| [i / cols, i % cols])
+                            .filter(|[r, c]| at(*r, *c) == Some(label))
+                            .collect();
+                        let corner = [
+                            squares.iter().map(|s| s[0]).min().unwrap(),
+                            squares.iter().map(|s| s[1]).min().unwrap(),
+                        ];
+                        (Shape::from_cells(&squares), corner)
+                    }
+                    Label::Small => (Block::Small.into(), [row, col]),
+                    Label::Wide => (Block::Wide.into(), [row, col]),
+                    _ => (Block::Large.into(), [row, col]),
+                };
+                if matches!(label, Label::Tall(_)) && shape != Block::Tall.into() {
+                    return Err(ParseGameError::BadShape { line, column });
+                }
+                for [r, c] in shape.squares(corner) {
+                    if at(r, c) != Some(label) || claimed[(r, c)] {
+                        return Err(ParseGameError::BadShape { line, column });
+                    }
+                    claimed[(r, c)] = true;
+                }
+                if label == Label::Large {
+                    larges.push(pieces.len());
+                }
+                let tall = match label {
+                    Label::Tall(i) => Some(i),
+                    _ => None,
+                };
+                pieces.push(((shape, corner), tall));
+            }
+        }
+        if larges.len() != 1 {
+            return Err(ParseGameError::LargeCount(larges.len()));
+        }
+
+        // Leave the tall pieces where they were found in the list, but sort them by number
+        let tall_slots: Vec<usize> = (0..pieces.len())
+            .filter(|i| pieces[*i].1.is_some())
+            .collect();
+        let mut talls: Vec<_> = tall_slots.iter().map(|i| pieces[*i].clone()).collect();
+        talls.sort_by_key(|(_, number)| *number);
+        for (slot, tall) in tall_slots.into_iter().zip(talls) {
+            pieces[slot] = tall;
+        }
+
+        let pieces: Vec<Placement> = pieces.into_iter().map(|(piece, _)| piece).collect();
+        let goal = [rows.saturating_sub(2), cols.saturating_sub(2) / 2];
+        Game::from_layout(rows, cols, &pieces, larges[0], goal).map_err(ParseGameError::Layout)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,160 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs the command line tool with `input` on stdin
+fn run(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sliding-puzzle-solver"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn scrambles_solves_and_verifies() {
+    let cases: [(&[&str], &[&str]); 2] = [
+        (&["scramble", "board", "--size", "3x4"], &["solve", "board"]),
+        (&["scramble", "soccer"], &["solve", "soccer", "--slide"]),
+    ];
+    for (scramble, solve) in cases {
+        let kind = scramble[1];
+        let scramble = [scramble, &["--seed", "7", "--steps", "40"]].concat();
+        let puzzle = stdout(&run(&scramble, ""));
+        assert_eq!(
+            stdout(&run(&scramble, "")),
+            puzzle,
+            "same seed, same scramble"
+        );
+
+        let moves = stdout(&run(solve, &puzzle));
+        let verified = run(&["verify", kind, "--moves", moves.trim()], &puzzle);
+        assert_eq!(stdout(&verified), "solved\n");
+    }
+}
+
+#[test]
+fn reports_unsolved_and_bad_input() {
+    let board = "  1  2\n  3  0\n";
+    let output = run(&["verify", "board", "--moves", "U"], board);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "not solved\n");
+
+    let output = run(&["solve", "board"], "1 2\n3 x\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 2, column 3"));
+
+    let output = run(&["solve", "cube"], "");
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = run(&["solve", "soccer"], "|LL|LL|\n|LL|LL|\n|SS|  |\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("can't reach the goal"));
+}
+
+#[test]
+fn solves_with_pattern_databases() {
+    let path = std::env::temp_dir().join(format!("sliding-puzzle-{}.pdb", std::process::id()));
+    let path = path.to_str().unwrap();
+    stdout(&run(
+        &["pdb", "board", "--size", "3x3", "--pattern", "4", path],
+        "",
+    ));
+
+    let board = stdout(&run(
+        &["scramble", "board", "--size", "3x3", "--seed", "3"],
+        "",
+    ));
+    let plain = stdout(&run(&["solve", "board", "--optimal"], &board));
+    let guided = stdout(&run(
+        &["solve", "board", "--optimal", "--pdb", path],
+        &board,
+    ));
+    assert_eq!(guided.len(), plain.len());
+    let verified = run(&["verify", "board", "--moves", guided.trim()], &board);
+    assert_eq!(stdout(&verified), "solved\n");
+
+    let output = run(
+        &["solve", "board", "--optimal", "--pdb", path],
+        "1 2\n3 0\n",
+    );
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = run(&["solve", "board", "--pdb", path], &board);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--pdb needs --optimal"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn solves_towards_other_goals() {
+    for seed in ["1", "2", "3", "4"] {
+        let steps: &[&str] = if seed == "4" { &["--steps", "30"] } else { &[] };
+        let scramble = [
+            &[
+                "scramble", "board", "--size", "3x4", "--goal", "spiral", "--seed", seed,
+            ],
+            steps,
+        ]
+        .concat();
+        let board = stdout(&run(&scramble, ""));
+        let moves = stdout(&run(&["solve", "board", "--goal", "spiral"], &board));
+        let verify = [
+            "verify",
+            "board",
+            "--goal",
+            "spiral",
+            "--moves",
+            moves.trim(),
+        ];
+        assert_eq!(stdout(&run(&verify, &board)), "solved\n");
+        let verify = ["verify", "board", "--moves", moves.trim()];
+        assert_eq!(run(&verify, &board).status.code(), Some(1));
+    }
+}
+
+#[test]
+fn verifies_the_blank_unless_told_not_to() {
+    let board = "  1  2\n  3  0\n";
+    let output = run(&["verify", "board", "--moves", "L"], board);
+    assert_eq!(output.status.code(), Some(1));
+    let output = run(
+        &["verify", "board", "--ignore-blank", "--moves", "L"],
+        board,
+    );
+    assert_eq!(stdout(&output), "solved\n");
+}
+
+#[test]
+fn prints_search_stats_and_progress() {
+    let board = stdout(&run(
+        &["scramble", "board", "--size", "3x3", "--seed", "5"],
+        "",
+    ));
+    let output = run(
+        &["solve", "board", "--optimal", "--stats", "--progress", "10"],
+        &board,
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.lines().any(|l| l.starts_with("... 10 expanded")));
+    assert!(stderr
+        .lines()
+        .any(|l| !l.starts_with("...") && l.contains("expanded")));
+}
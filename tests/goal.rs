@@ -0,0 +1,130 @@
+use array2d::Array2D;
+use sliding_puzzle_solver::search::a_star;
+use sliding_puzzle_solver::{Board, BoardError, Goal, PatternDatabase, Puzzle, SolvedCheck};
+
+const GOALS: [Goal; 4] = [Goal::Standard, Goal::BlankFirst, Goal::Snake, Goal::Spiral];
+
+fn rows(layout: &Array2D<u32>) -> Vec<Vec<u32>> {
+    layout.as_rows()
+}
+
+/// Boards scrambled by walking away from the goal, with the goal set
+fn boards(goal: Goal, rows: usize, cols: usize) -> impl Iterator<Item = Board> {
+    (0..).map(move |seed| {
+        let mut board = Board::new(goal.layout(rows, cols));
+        board.random_walk(rows * cols * 3, seed);
+        board.set_goal(goal.layout(rows, cols)).unwrap();
+        board
+    })
+}
+
+#[test]
+fn lays_out_goals() {
+    assert_eq!(
+        rows(&Goal::BlankFirst.layout(2, 3)),
+        [vec![0, 1, 2], vec![3, 4, 5]]
+    );
+    assert_eq!(
+        rows(&Goal::Snake.layout(3, 3)),
+        [vec![1, 2, 3], vec![6, 5, 4], vec![7, 8, 0]]
+    );
+    assert_eq!(
+        rows(&Goal::Spiral.layout(3, 3)),
+        [vec![1, 2, 3], vec![8, 0, 4], vec![7, 6, 5]]
+    );
+    assert_eq!(
+        rows(&Goal::Spiral.layout(4, 4)),
+        [
+            vec![1, 2, 3, 4],
+            vec![12, 13, 14, 5],
+            vec![11, 0, 15, 6],
+            vec![10, 9, 8, 7]
+        ]
+    );
+    assert_eq!(
+        rows(&Goal::Spiral.layout(2, 4)),
+        [vec![1, 2, 3, 4], vec![0, 7, 6, 5]]
+    );
+}
+
+#[test]
+fn reduction_reaches_every_goal() {
+    for goal in GOALS {
+        for (rows, cols) in [(2, 2), (3, 3), (4, 4), (3, 5), (6, 4)] {
+            for mut board in boards(goal, rows, cols).take(5) {
+                board.solve().unwrap();
+                assert!(
+                    board.is_goal(&board.state()),
+                    "{:?} {}x{}",
+                    goal,
+                    rows,
+                    cols
+                );
+                assert!(board.check_solved());
+            }
+        }
+    }
+}
+
+#[test]
+fn optimal_solvers_reach_every_goal() {
+    for goal in GOALS {
+        let db =
+            PatternDatabase::build_for_goal(&goal.layout(3, 3), &PatternDatabase::split(3, 3, 4))
+                .unwrap();
+        for board in boards(goal, 3, 3).take(3) {
+            let shortest = a_star(&board).unwrap().len();
+            let mut optimal = board.clone();
+            optimal.solve_optimal().unwrap();
+            assert!(optimal.is_goal(&optimal.state()));
+            assert_eq!(optimal.move_sequence().len(), shortest, "{:?}", goal);
+
+            let mut guided = board.clone();
+            guided.solve_optimal_with(&db).unwrap();
+            assert!(guided.is_goal(&guided.state()));
+            assert_eq!(guided.move_sequence().len(), shortest, "{:?}", goal);
+        }
+    }
+}
+
+#[test]
+fn solvability_depends_on_the_goal() {
+    let mut board = Board::solved(3, 3).unwrap();
+    assert!(board.is_solvable());
+    let swapped = Array2D::from_rows(&[vec![2, 1, 3], vec![4, 5, 6], vec![7, 8, 0]]).unwrap();
+    board.set_goal(swapped).unwrap();
+    assert!(!board.is_solvable());
+    assert!(board.solve().is_err());
+    assert!(!board.check_solved());
+}
+
+#[test]
+fn rejects_bad_goals() {
+    let mut board = Board::solved(3, 3).unwrap();
+    assert_eq!(
+        board.set_goal(Goal::Standard.layout(2, 3)),
+        Err(BoardError::GoalSize { rows: 2, cols: 3 })
+    );
+    let repeated = Array2D::from_rows(&[vec![1, 1, 3], vec![4, 5, 6], vec![7, 8, 0]]).unwrap();
+    assert_eq!(board.set_goal(repeated), Err(BoardError::DuplicateTile(1)));
+    assert_eq!(board.goal(), &Goal::Standard.layout(3, 3));
+}
+
+#[test]
+fn strict_check_needs_the_blank_in_place() {
+    let mut board = Board::from_rows(&[vec![1, 2, 3], vec![4, 0, 5], vec![6, 7, 8]]).unwrap();
+    assert!(board.check_solved_with(SolvedCheck::IgnoreBlank));
+    assert!(!board.check_solved_with(SolvedCheck::Strict));
+    assert!(!board.check_solved());
+
+    board.solve().unwrap();
+    assert!(board.check_solved());
+    assert_eq!(board.to_string(), Board::solved(3, 3).unwrap().to_string());
+
+    // Lenient boards still get their blank moved to where the goal has it
+    let mut board = Board::from_rows(&[vec![1, 2], vec![0, 3]]).unwrap();
+    assert!(board.check_solved_with(SolvedCheck::IgnoreBlank));
+    board.solve().unwrap();
+    assert_eq!(board.print_moves(), "R,");
+    assert!(board.check_solved());
+}
@@ -0,0 +1,110 @@
+use array2d::Array2D;
+use sliding_puzzle_solver::{Board, BoardError, IllegalMove, Move, ParseBoardError, Puzzle};
+
+fn corner_board() -> Board {
+    Board::from_rows(&[vec![0, 1, 2], vec![3, 4, 5], vec![6, 7, 8]]).unwrap()
+}
+
+#[test]
+fn rejects_moves_off_the_edge() {
+    let mut board = corner_board();
+    let before = board.to_string();
+    assert_eq!(board.apply(Move::Up), Err(IllegalMove(Move::Up)));
+    assert_eq!(board.apply(Move::Left), Err(IllegalMove(Move::Left)));
+    assert_eq!(board.to_string(), before);
+    assert_eq!(board.print_moves(), "");
+}
+
+#[test]
+fn legal_moves_follow_the_blank() {
+    let mut board = corner_board();
+    assert_eq!(board.legal_moves(), vec![Move::Down, Move::Right]);
+    board.apply(Move::Right).unwrap();
+    board.apply(Move::Down).unwrap();
+    assert_eq!(
+        board.legal_moves(),
+        vec![Move::Up, Move::Down, Move::Left, Move::Right]
+    );
+}
+
+#[test]
+fn undo_restores_the_board() {
+    let mut board = corner_board();
+    let before = board.to_string();
+    board.apply(Move::Right).unwrap();
+    board.apply(Move::Down).unwrap();
+    assert_eq!(board.undo(), Some(Move::Down));
+    assert_eq!(board.undo(), Some(Move::Right));
+    assert_eq!(board.undo(), None);
+    assert_eq!(board.to_string(), before);
+    assert_eq!(board.print_moves(), "");
+}
+
+#[test]
+fn parses_displayed_boards() {
+    let board = corner_board();
+    let parsed: Board = board.to_string().parse().unwrap();
+    assert_eq!(parsed.to_string(), board.to_string());
+
+    let parsed: Board = "\n 1 2\n 3   0\n\n".parse().unwrap();
+    assert_eq!(parsed.to_string(), "  1  2\n  3  0\n");
+
+    // Three-digit tiles still get a space between them
+    for (rows, cols) in [(11, 10), (20, 20), (32, 32)] {
+        let board = Board::scrambled(rows, cols, 1).unwrap();
+        let text = board.to_string();
+        let parsed: Board = text.parse().unwrap();
+        assert_eq!(parsed.to_string(), text, "{}x{}", rows, cols);
+        assert_eq!(parsed.state(), board.state());
+    }
+    let wide = Board::solved(10, 11).unwrap().to_string();
+    assert!(wide.ends_with(" 108 109   0\n"));
+
+    let parse = |s: &str| s.parse::<Board>().unwrap_err();
+    assert_eq!(
+        parse("1 2\n3 -0"),
+        ParseBoardError::InvalidTile { line: 2, column: 3 }
+    );
+    assert_eq!(parse("  \n"), ParseBoardError::Board(BoardError::Empty));
+    assert_eq!(
+        parse("1 2\n3"),
+        ParseBoardError::Board(BoardError::NotRectangular)
+    );
+    assert_eq!(
+        parse("1 2\n3 3"),
+        ParseBoardError::Board(BoardError::MissingBlank)
+    );
+}
+
+#[test]
+fn rejects_bad_boards() {
+    assert_eq!(
+        Board::from_rows(&[vec![1, 2, 3], vec![4, 9, 0]]).unwrap_err(),
+        BoardError::OutOfRange(9)
+    );
+    assert_eq!(
+        Board::from_rows(&[vec![1, 2, 2], vec![4, 5, 0]]).unwrap_err(),
+        BoardError::DuplicateTile(2)
+    );
+    assert_eq!(
+        Board::from_rows(&[vec![1, 2, 3, 0]]).unwrap_err(),
+        BoardError::TooSmall { rows: 1, cols: 4 }
+    );
+    assert_eq!(
+        Board::from_rows(&[vec![1], vec![0]]).unwrap_err(),
+        BoardError::TooSmall { rows: 2, cols: 1 }
+    );
+    assert_eq!(Board::from_rows(&[]).unwrap_err(), BoardError::Empty);
+    assert_eq!(
+        Board::try_new(Array2D::from_rows(&[vec![], vec![]]).unwrap()).unwrap_err(),
+        BoardError::Empty
+    );
+    assert_eq!(
+        Board::try_new(Array2D::from_rows(&[vec![1, 2], vec![3, 4]]).unwrap()).unwrap_err(),
+        BoardError::MissingBlank
+    );
+    assert_eq!(
+        Board::from_rows(&[vec![1, 2], vec![0]]).unwrap_err(),
+        BoardError::NotRectangular
+    );
+}
@@ -0,0 +1,111 @@
+use std::io::ErrorKind;
+
+use sliding_puzzle_solver::{Board, PatternDatabase, PatternError};
+
+#[test]
+fn splits_tiles_into_patterns() {
+    let sizes: Vec<usize> = PatternDatabase::split(4, 4, 6)
+        .iter()
+        .map(Vec::len)
+        .collect();
+    assert_eq!(sizes, [6, 6, 3]);
+    let sizes: Vec<usize> = PatternDatabase::split(5, 5, 6)
+        .iter()
+        .map(Vec::len)
+        .collect();
+    assert_eq!(sizes, [6, 6, 6, 6]);
+}
+
+#[test]
+fn finds_shortest_solutions() {
+    for (rows, cols, size) in [(3, 3, 4), (3, 4, 4), (4, 3, 6)] {
+        let db =
+            PatternDatabase::build(rows, cols, &PatternDatabase::split(rows, cols, size)).unwrap();
+        for seed in 0..4 {
+            let board = Board::scrambled(rows, cols, seed).unwrap();
+            let mut expected = board.clone();
+            expected.solve_optimal().unwrap();
+            let mut solved = board.clone();
+            solved.solve_optimal_with(&db).unwrap();
+            assert!(solved.check_solved(), "{}x{} seed {}", rows, cols, seed);
+            assert_eq!(
+                solved.move_sequence().len(),
+                expected.move_sequence().len(),
+                "{}x{} seed {}",
+                rows,
+                cols,
+                seed
+            );
+        }
+    }
+}
+
+#[test]
+fn solves_fifteen_puzzles() {
+    let db = PatternDatabase::build(4, 4, &PatternDatabase::split(4, 4, 3)).unwrap();
+    for seed in 0..4 {
+        let mut board = Board::solved(4, 4).unwrap();
+        board.random_walk(40, seed);
+        let mut expected = board.clone();
+        expected.solve_optimal().unwrap();
+        board.solve_optimal_with(&db).unwrap();
+        assert!(board.check_solved());
+        assert_eq!(board.move_sequence().len(), expected.move_sequence().len());
+    }
+}
+
+#[test]
+fn round_trips_through_bytes() {
+    let db = PatternDatabase::build(3, 3, &[vec![1, 2, 3], vec![4, 7], vec![5, 6, 8]]).unwrap();
+    let mut bytes = vec![];
+    db.write_to(&mut bytes).unwrap();
+    // Header and goal, then the tiles and one byte per placement of each pattern
+    assert_eq!(
+        bytes.len(),
+        11 + 9 + (4 + 3 + 9 * 8 * 7) + (4 + 2 + 9 * 8) + (4 + 3 + 9 * 8 * 7)
+    );
+    assert_eq!(PatternDatabase::read_from(bytes.as_slice()).unwrap(), db);
+
+    let truncated = PatternDatabase::read_from(&bytes[..bytes.len() - 1]).unwrap_err();
+    assert_eq!(truncated.kind(), ErrorKind::UnexpectedEof);
+    let mut wrong_magic = bytes.clone();
+    wrong_magic[0] = b'X';
+    let wrong_magic = PatternDatabase::read_from(wrong_magic.as_slice()).unwrap_err();
+    assert_eq!(wrong_magic.kind(), ErrorKind::InvalidData);
+
+    // A header claiming a huge board is refused before any table is read
+    let mut huge = bytes[..7].to_vec();
+    huge[5..7].copy_from_slice(&[255, 255]);
+    huge.extend(std::iter::repeat_n(0, 255 * 255));
+    huge.extend(u32::MAX.to_le_bytes());
+    let huge = PatternDatabase::read_from(huge.as_slice()).unwrap_err();
+    assert_eq!(huge.kind(), ErrorKind::InvalidData);
+    let mut bad_goal = bytes.clone();
+    bad_goal[7] = bad_goal[8];
+    let bad_goal = PatternDatabase::read_from(&bad_goal[..16]).unwrap_err();
+    assert_eq!(bad_goal.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn rejects_bad_patterns() {
+    assert_eq!(
+        PatternDatabase::build(3, 3, &[vec![0, 1]]),
+        Err(PatternError::InvalidTile(0))
+    );
+    assert_eq!(
+        PatternDatabase::build(3, 3, &[vec![9]]),
+        Err(PatternError::InvalidTile(9))
+    );
+    assert_eq!(
+        PatternDatabase::build(3, 3, &[vec![1, 2], vec![2, 3]]),
+        Err(PatternError::DuplicateTile(2))
+    );
+    assert_eq!(
+        PatternDatabase::build(1, 3, &[vec![1]]),
+        Err(PatternError::TooSmall { rows: 1, cols: 3 })
+    );
+    assert_eq!(
+        PatternDatabase::build(6, 6, &[vec![1]]),
+        Err(PatternError::TooBig { rows: 6, cols: 6 })
+    );
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use sliding_puzzle_solver::{Board, BoardError, Goal, Metric, SoccerPuzzle};
+
+#[test]
+fn scrambles_are_solvable_and_repeatable() {
+    for (rows, cols) in [(2, 2), (3, 3), (3, 5), (4, 4), (6, 4)] {
+        for seed in 0..20 {
+            let board = Board::scrambled(rows, cols, seed).unwrap();
+            assert!(board.is_solvable());
+            assert!(board.print_moves().is_empty());
+            assert_eq!(
+                board.to_string(),
+                Board::scrambled(rows, cols, seed).unwrap().to_string()
+            );
+        }
+        assert_ne!(
+            Board::scrambled(rows, cols, 1).unwrap().to_string(),
+            Board::scrambled(rows, cols, 2).unwrap().to_string()
+        );
+    }
+    assert_eq!(
+        Board::scrambled(1, 3, 0).unwrap_err(),
+        BoardError::TooSmall { rows: 1, cols: 3 }
+    );
+}
+
+#[test]
+fn scrambles_are_uniform() {
+    // A 2x2 board has 12 solvable positions, which should come up about equally often
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for seed in 0..12_000 {
+        *counts
+            .entry(Board::scrambled(2, 2, seed).unwrap().to_string())
+            .or_default() += 1;
+    }
+    assert_eq!(counts.len(), 12);
+    for (board, count) in counts {
+        assert!((850..1150).contains(&count), "{count} of\n{board}");
+    }
+}
+
+#[test]
+fn random_walks_are_repeatable() {
+    let solved = Board::solved(4, 4).unwrap();
+    assert!(solved.check_solved());
+
+    let mut board = solved.clone();
+    board.random_walk(0, 5);
+    assert_eq!(board.to_string(), solved.to_string());
+
+    board.random_walk(1, 5);
+    assert_ne!(board.to_string(), solved.to_string());
+
+    let mut a = solved.clone();
+    let mut b = solved.clone();
+    a.random_walk(100, 9);
+    b.random_walk(100, 9);
+    assert_eq!(a.to_string(), b.to_string());
+    assert!(a.print_moves().is_empty());
+    assert!(a.is_solvable());
+    a.solve().unwrap();
+    assert!(a.check_solved());
+}
+
+#[test]
+fn random_walks_scramble_soccer() {
+    for metric in [Metric::Step, Metric::Slide] {
+        let mut game = SoccerPuzzle::new();
+        game.metric = metric;
+        game.check_invariants = true;
+        let mut again = game.clone();
+        game.random_walk(150, 3);
+        again.random_walk(150, 3);
+        assert_eq!(game.to_string(), again.to_string());
+        assert_ne!(game.to_string(), SoccerPuzzle::new().to_string());
+        assert!(game.moves.is_empty());
+        game.solve().unwrap();
+    }
+}
+
+#[test]
+fn scrambles_towards_any_goal() {
+    for goal in [Goal::BlankFirst, Goal::Snake, Goal::Spiral] {
+        for seed in 0..10 {
+            let board = Board::scrambled_towards(goal.layout(3, 4), seed).unwrap();
+            assert_eq!(board.goal(), &goal.layout(3, 4));
+            assert!(board.is_solvable(), "{:?} seed {}", goal, seed);
+        }
+    }
+}
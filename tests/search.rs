@@ -0,0 +1,132 @@
+use sliding_puzzle_solver::search::{
+    a_star, a_star_monitored, bfs, bfs_monitored, ida_star, ida_star_monitored,
+};
+use sliding_puzzle_solver::{Board, Monitor, Puzzle, SoccerPuzzle};
+
+#[test]
+fn searches_agree_on_board_solution_length() {
+    let rows = [vec![8, 6, 7], vec![2, 5, 4], vec![3, 0, 1]];
+    let board = Board::from_rows(&rows).unwrap();
+
+    let mut optimal = board.clone();
+    optimal.solve_optimal().unwrap();
+    let shortest = optimal.print_moves().len() / 2;
+
+    for moves in [bfs(&board), a_star(&board), ida_star(&board)] {
+        let moves = moves.unwrap();
+        assert_eq!(moves.len(), shortest);
+        let mut replayed = board.clone();
+        for m in moves {
+            replayed.apply(m).unwrap();
+        }
+        assert!(replayed.is_goal(&replayed.state()));
+    }
+}
+
+#[test]
+fn unsolvable_board_has_no_solution() {
+    let board = Board::from_rows(&[vec![2, 1], vec![3, 0]]).unwrap();
+    assert_eq!(bfs(&board), None);
+    assert_eq!(a_star(&board), None);
+    assert_eq!(ida_star(&board), None);
+}
+
+#[test]
+fn solves_soccer_puzzle() {
+    let mut game = SoccerPuzzle::new();
+    game.solve().unwrap();
+    assert!(game.is_goal(&game.state()));
+}
+
+#[test]
+fn soccer_a_star_matches_bfs_length() {
+    let game = SoccerPuzzle::new();
+    let shortest = bfs(&game).unwrap().len();
+    let mut solved = game.clone();
+    solved.solve().unwrap();
+    assert_eq!(solved.moves.len(), shortest);
+}
+
+#[test]
+fn finds_known_optimal_lengths() {
+    // The two hardest 8-puzzle positions need 31 moves
+    for rows in [
+        [vec![8, 6, 7], vec![2, 5, 4], vec![3, 0, 1]],
+        [vec![6, 4, 7], vec![8, 5, 0], vec![3, 2, 1]],
+    ] {
+        let mut board = Board::from_rows(&rows).unwrap();
+        board.solve_optimal().unwrap();
+        assert!(board.check_solved());
+        assert_eq!(board.move_sequence().len(), 31);
+    }
+
+    let mut board = Board::from_rows(&[vec![1, 2, 3], vec![4, 5, 6], vec![0, 7, 8]]).unwrap();
+    board.solve_optimal().unwrap();
+    assert_eq!(board.print_moves(), "R,R,");
+}
+
+#[test]
+fn searches_report_stats() {
+    let rows = [vec![8, 6, 7], vec![2, 5, 4], vec![3, 0, 1]];
+    let board = Board::from_rows(&rows).unwrap();
+    let searches: [fn(&Board, &mut Monitor) -> Option<Vec<_>>; 3] =
+        [bfs_monitored, a_star_monitored, ida_star_monitored];
+    let mut expanded = vec![];
+    for search in searches {
+        let mut monitor = Monitor::new();
+        let moves = search(&board, &mut monitor).unwrap();
+        let stats = monitor.stats();
+        assert!(stats.nodes_generated > stats.nodes_expanded);
+        assert!(stats.duplicates_pruned > 0);
+        assert!(stats.max_frontier > 0);
+        assert!(stats.peak_memory > 0);
+        assert_eq!(stats.max_depth, moves.len() - 1);
+        expanded.push(stats.nodes_expanded);
+    }
+    // The heuristic keeps A* well away from most of what breadth-first search looks at
+    assert!(expanded[1] < expanded[0] / 10);
+}
+
+#[test]
+fn solvers_report_stats() {
+    let board = Board::scrambled(4, 4, 3).unwrap();
+    let mut monitor = Monitor::new();
+    board.clone().solve_monitored(&mut monitor).unwrap();
+    let reduction = monitor.stats();
+    assert!(reduction.nodes_expanded > 0);
+    assert!(reduction.nodes_generated > reduction.nodes_expanded);
+
+    // Stats keep adding up when a monitor is used again
+    board.clone().solve_monitored(&mut monitor).unwrap();
+    assert_eq!(monitor.stats().nodes_expanded, 2 * reduction.nodes_expanded);
+    assert!(monitor.stats().elapsed >= reduction.elapsed);
+
+    let mut board = Board::from_rows(&[vec![8, 6, 7], vec![2, 5, 4], vec![3, 0, 1]]).unwrap();
+    let mut monitor = Monitor::new();
+    board.solve_optimal_monitored(None, &mut monitor).unwrap();
+    let optimal = monitor.stats();
+    assert_eq!(optimal.max_depth, board.move_sequence().len() - 1);
+    assert_eq!(optimal.max_frontier, board.move_sequence().len());
+    assert!(optimal.duplicates_pruned > 0);
+
+    let mut game = SoccerPuzzle::new();
+    let mut monitor = Monitor::new();
+    game.solve_monitored(&mut monitor).unwrap();
+    assert!(monitor.stats().nodes_expanded > 0);
+    assert!(monitor.stats().max_frontier > 0);
+}
+
+#[test]
+fn reports_progress() {
+    let board = Board::from_rows(&[vec![8, 6, 7], vec![2, 5, 4], vec![3, 0, 1]]).unwrap();
+    let mut reports = vec![];
+    let mut monitor = Monitor::with_progress(100, |stats| reports.push(stats.nodes_expanded));
+    bfs_monitored(&board, &mut monitor).unwrap();
+    let expanded = monitor.stats().nodes_expanded;
+    drop(monitor);
+    assert_eq!(reports.len() as u64, expanded / 100);
+    assert!(reports
+        .iter()
+        .enumerate()
+        .all(|(i, n)| *n == (i as u64 + 1) * 100));
+}
@@ -0,0 +1,87 @@
+use sliding_puzzle_solver::{Board, Move, MoveSequence, ParseMoveError};
+
+#[test]
+fn parses_every_format() {
+    let expected = MoveSequence::from(vec![
+        Move::Left,
+        Move::Left,
+        Move::Left,
+        Move::Up,
+        Move::Up,
+        Move::Right,
+    ]);
+    for s in ["L,L,L,U,U,R,", "LLLUUR", "L3U2R", "l3, u2, r"] {
+        assert_eq!(s.parse::<MoveSequence>(), Ok(expected.clone()), "{s}");
+    }
+    assert_eq!(expected.to_string(), "L,L,L,U,U,R,");
+}
+
+#[test]
+fn reports_bad_input() {
+    assert_eq!(
+        "LRX".parse::<MoveSequence>(),
+        Err(ParseMoveError::UnknownMove { pos: 2, found: 'X' })
+    );
+    assert_eq!(
+        "L0".parse::<MoveSequence>(),
+        Err(ParseMoveError::InvalidCount { pos: 1 })
+    );
+    assert_eq!(
+        "U,L18446744073709551615".parse::<MoveSequence>(),
+        Err(ParseMoveError::InvalidCount { pos: 3 })
+    );
+    assert_eq!(
+        "D99999999999999999999999".parse::<MoveSequence>(),
+        Err(ParseMoveError::InvalidCount { pos: 1 })
+    );
+    assert_eq!("R65536".parse::<MoveSequence>().unwrap().len(), 65536);
+    assert_eq!(
+        "R65537".parse::<MoveSequence>(),
+        Err(ParseMoveError::InvalidCount { pos: 1 })
+    );
+    assert_eq!("".parse::<Move>(), Err(ParseMoveError::Empty));
+    assert_eq!("U".parse::<Move>(), Ok(Move::Up));
+}
+
+#[test]
+fn replays_a_stored_solution() {
+    let rows = [vec![4, 1, 3], vec![7, 2, 5], vec![0, 8, 6]];
+    let mut board = Board::from_rows(&rows).unwrap();
+    board.solve().unwrap();
+    let solution: MoveSequence = board.print_moves().parse().unwrap();
+
+    let mut replayed = Board::from_rows(&rows).unwrap();
+    replayed.apply_sequence(&solution).unwrap();
+    assert_eq!(replayed.to_string(), board.to_string());
+    assert!(replayed.check_solved());
+}
+
+#[test]
+fn simplify_cuts_out_loops() {
+    // Going around a 2x2 square three times puts every tile back where it started
+    let mut seq: MoveSequence = "LR RDLU RDLU RDLU UD L".parse().unwrap();
+    assert_eq!(seq.simplify(), 16);
+    assert_eq!(seq.to_string(), "L,");
+
+    let mut seq: MoveSequence = "RDLU".parse().unwrap();
+    assert_eq!(seq.simplify(), 0);
+}
+
+#[test]
+fn solve_reports_saved_moves() {
+    let rows = [
+        vec![16, 12, 7, 0, 5],
+        vec![6, 10, 4, 1, 9],
+        vec![21, 11, 24, 19, 3],
+        vec![17, 14, 2, 18, 15],
+        vec![13, 23, 22, 8, 20],
+    ];
+    let mut board = Board::from_rows(&rows).unwrap();
+    board.solve().unwrap();
+    let mut solution = board.move_sequence();
+    assert_eq!(solution.simplify(), 0);
+
+    let mut replayed = Board::from_rows(&rows).unwrap();
+    replayed.apply_sequence(&solution).unwrap();
+    assert!(replayed.check_solved());
+}
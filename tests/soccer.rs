@@ -0,0 +1,476 @@
+use sliding_puzzle_solver::{
+    Block, BlockedMove, Direction, LayoutError, Metric, NoSolution, ParseGameError,
+    ParsePieceMoveError, PieceMove, Placement, Puzzle, Shape, SoccerPuzzle,
+};
+
+fn place(block: Block, corner: [usize; 2]) -> Placement {
+    (block.into(), corner)
+}
+
+#[test]
+fn solves_huarong_dao_in_116_steps() {
+    let mut game = SoccerPuzzle::huarong_dao();
+    game.solve().unwrap();
+    assert_eq!(game.moves.len(), 116);
+    assert!(game.is_goal(&game.state()));
+}
+
+#[test]
+fn solves_huarong_dao_in_81_slides() {
+    let mut game = SoccerPuzzle::huarong_dao();
+    game.metric = Metric::Slide;
+    game.solve().unwrap();
+    assert_eq!(game.moves.len(), 81);
+    assert!(game.is_goal(&game.state()));
+
+    // Replaying one square at a time reaches the same position
+    let mut replay = SoccerPuzzle::huarong_dao();
+    for m in &game.moves {
+        for dir in &m.path {
+            replay.make_move(PieceMove {
+                piece: m.piece,
+                path: vec![*dir],
+            });
+        }
+    }
+    assert_eq!(replay.to_string(), game.to_string());
+    assert!(replay.moves.len() >= 116);
+}
+
+#[test]
+fn lists_slides_around_corners() {
+    // |LL|LL|  |
+    // |LL|LL|SS|
+    // |  |  |  |
+    let mut game = SoccerPuzzle::from_layout(
+        3,
+        3,
+        &[place(Block::Large, [0, 0]), place(Block::Small, [1, 2])],
+        0,
+        [1, 1],
+    )
+    .unwrap();
+    game.metric = Metric::Slide;
+    let slides: Vec<_> = game
+        .list_moves()
+        .into_iter()
+        .filter(|m| m.piece == 1)
+        .map(|m| m.path)
+        .collect();
+    assert_eq!(
+        slides,
+        vec![
+            vec![Direction::Up],
+            vec![Direction::Down],
+            vec![Direction::Down, Direction::Left],
+            vec![Direction::Down, Direction::Left, Direction::Left],
+        ]
+    );
+
+    game.solve().unwrap();
+    assert_eq!(game.moves.len(), 2);
+}
+
+#[test]
+fn solves_custom_layout() {
+    // The large piece only has to slide down once the blanks are below it
+    let mut game = SoccerPuzzle::from_layout(
+        3,
+        3,
+        &[
+            place(Block::Large, [0, 0]),
+            place(Block::Tall, [0, 2]),
+            place(Block::Small, [2, 2]),
+        ],
+        0,
+        [1, 0],
+    )
+    .unwrap();
+    game.solve().unwrap();
+    assert!(game.is_goal(&game.state()));
+    assert_eq!(game.moves.len(), 1);
+}
+
+#[test]
+fn rejects_bad_layouts() {
+    let large = place(Block::Large, [0, 0]);
+    let layout = |pieces: &[Placement], target, goal| {
+        SoccerPuzzle::from_layout(3, 3, pieces, target, goal).unwrap_err()
+    };
+    assert_eq!(
+        layout(&[large.clone(), place(Block::Tall, [0, 1])], 0, [1, 1]),
+        LayoutError::Overlap([0, 1])
+    );
+    assert_eq!(
+        layout(&[large.clone(), place(Block::Wide, [2, 2])], 0, [1, 1]),
+        LayoutError::OutOfBounds(1)
+    );
+    assert_eq!(
+        layout(
+            &[large.clone(), (Shape::from_cells(&[]), [2, 2])],
+            0,
+            [1, 1]
+        ),
+        LayoutError::EmptyPiece(1)
+    );
+    assert_eq!(
+        layout(
+            &[
+                large.clone(),
+                (Shape::rect(1, 3), [0, 2]),
+                (Shape::rect(2, 1), [2, 0])
+            ],
+            0,
+            [0, 0]
+        ),
+        LayoutError::BlankCount(0)
+    );
+    let full = [
+        large,
+        place(Block::Tall, [0, 2]),
+        place(Block::Small, [2, 0]),
+    ];
+    assert_eq!(layout(&full, 3, [0, 0]), LayoutError::UnknownTarget(3));
+    assert_eq!(layout(&full, 0, [2, 2]), LayoutError::GoalOutOfBounds);
+
+    // 8 bits per piece on a 12x12 board, so 17 pieces don't fit in a u128
+    let smalls = vec![place(Block::Small, [0, 0]); 17];
+    assert_eq!(
+        SoccerPuzzle::from_layout(12, 12, &smalls, 0, [0, 0]).unwrap_err(),
+        LayoutError::TooBig { rows: 12, cols: 12 }
+    );
+}
+
+#[test]
+fn solves_with_one_blank() {
+    let small = |corner| place(Block::Small, corner);
+    let mut game = SoccerPuzzle::from_layout(
+        2,
+        2,
+        &[small([0, 0]), small([0, 1]), small([1, 0])],
+        0,
+        [1, 1],
+    )
+    .unwrap();
+    assert_eq!(game.blanks(), [[1, 1]]);
+    game.solve().unwrap();
+    assert!(game.is_goal(&game.state()));
+    assert_eq!(game.moves.len(), 5);
+    assert_eq!(game.blanks().len(), 1);
+}
+
+#[test]
+fn solves_with_many_blanks() {
+    let mut game = SoccerPuzzle::from_layout(
+        3,
+        3,
+        &[place(Block::Large, [0, 0]), place(Block::Small, [1, 2])],
+        0,
+        [1, 1],
+    )
+    .unwrap();
+    assert_eq!(game.blanks(), [[0, 2], [2, 0], [2, 1], [2, 2]]);
+    assert_eq!(
+        game.list_moves(),
+        vec![
+            PieceMove {
+                piece: 0,
+                path: vec![Direction::Down],
+            },
+            PieceMove {
+                piece: 1,
+                path: vec![Direction::Up],
+            },
+            PieceMove {
+                piece: 1,
+                path: vec![Direction::Down],
+            },
+        ]
+    );
+    game.make_move(PieceMove {
+        piece: 0,
+        path: vec![Direction::Down],
+    });
+    assert_eq!(game.blanks(), [[0, 0], [0, 1], [0, 2], [2, 2]]);
+
+    game.solve().unwrap();
+    assert!(game.is_goal(&game.state()));
+    assert_eq!(game.moves.len(), 3);
+}
+
+#[test]
+fn moves_any_shape() {
+    // |SS|P1|P2|
+    // |P1|P1|P2|
+    // |  |  |P2|
+    let corner = Shape::from_cells(&[[0, 1], [1, 0], [1, 1]]);
+    let mut game = SoccerPuzzle::from_layout(
+        3,
+        3,
+        &[
+            place(Block::Small, [0, 0]),
+            (corner, [0, 0]),
+            (Shape::rect(1, 3), [0, 2]),
+        ],
+        1,
+        [1, 0],
+    )
+    .unwrap();
+    assert_eq!(game.piece_at([0, 0]), Some(0));
+    assert_eq!(game.piece_at([2, 2]), Some(2));
+    assert_eq!(game.piece_at([2, 0]), None);
+    assert_eq!(
+        game.list_moves(),
+        vec![PieceMove {
+            piece: 1,
+            path: vec![Direction::Down],
+        }]
+    );
+    assert_eq!(game.to_string(), "|SS|P1|P2|\n|P1|P1|P2|\n|  |  |P2|\n");
+
+    game.solve().unwrap();
+    assert_eq!(game.moves.len(), 1);
+    assert_eq!(game.piece_at([2, 0]), Some(1));
+    assert_eq!(game.piece_at([0, 1]), None);
+}
+
+#[test]
+fn parses_displayed_layouts() {
+    for game in [SoccerPuzzle::new(), SoccerPuzzle::huarong_dao()] {
+        let text = game.to_string();
+        let parsed: SoccerPuzzle = text.parse().unwrap();
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(parsed.state(), game.state());
+    }
+
+    // Tall numbers are kept as written
+    let text = "|T3|LL|LL|T1|\n|T3|LL|LL|T1|\n|  |WW|WW|  |\n|T0|SS|SS|T2|\n|T0|SS|SS|T2|\n";
+    let parsed: SoccerPuzzle = text.parse().unwrap();
+    assert_eq!(parsed.to_string(), text);
+}
+
+#[test]
+fn reports_parse_errors_with_position() {
+    let parse = |s: &str| s.parse::<SoccerPuzzle>().unwrap_err();
+    assert_eq!(parse(""), ParseGameError::Empty);
+    assert_eq!(
+        parse("|LL|LL|\n|LL|XX|"),
+        ParseGameError::UnknownPiece { line: 2, column: 5 }
+    );
+    assert_eq!(
+        parse("|LL|LL|\n|LL|LL"),
+        ParseGameError::MissingSeparator { line: 2, column: 7 }
+    );
+    assert_eq!(
+        parse("|LL|LL|\n|LL|LL|  |"),
+        ParseGameError::RowLength {
+            line: 2,
+            expected: 2,
+            found: 3
+        }
+    );
+    assert_eq!(
+        parse("|T0|LL|LL|\n|T1|LL|LL|\n|  |  |SS|"),
+        ParseGameError::BadShape { line: 1, column: 2 }
+    );
+    assert_eq!(
+        parse("|WW|LL|LL|\n|SS|LL|LL|\n|  |  |SS|"),
+        ParseGameError::BadShape { line: 1, column: 2 }
+    );
+    assert_eq!(
+        parse("|LL|LL|\n|LL|LL|\n|SS|SS|"),
+        ParseGameError::Layout(LayoutError::BlankCount(0))
+    );
+    assert_eq!(
+        parse("|LL|LL|  |\n|LL|LL|  |\n\nthis is not a layout\n"),
+        ParseGameError::TrailingText { line: 4 }
+    );
+    assert_eq!(
+        parse("\n|LL|LL|  |\n|LL|LL|  |\n \n\n|LL|LL|  |\n|LL|LL|  |\n"),
+        ParseGameError::TrailingText { line: 6 }
+    );
+    assert!("\n|LL|LL|  |\n|LL|LL|  |\n\n  \n"
+        .parse::<SoccerPuzzle>()
+        .is_ok());
+}
+
+#[test]
+fn keeps_blanks_consistent_while_moving() {
+    let layouts = [
+        SoccerPuzzle::new(),
+        SoccerPuzzle::huarong_dao(),
+        SoccerPuzzle::from_layout(
+            3,
+            3,
+            &[place(Block::Large, [0, 0]), place(Block::Small, [1, 2])],
+            0,
+            [1, 1],
+        )
+        .unwrap(),
+    ];
+    for metric in [Metric::Step, Metric::Slide] {
+        for layout in &layouts {
+            let mut game = layout.clone();
+            game.metric = metric;
+            game.check_invariants = true;
+            assert_eq!(game.verify(), Ok(()));
+            // Walk through the positions without repeating a choice pattern, so most pieces get moved
+            for i in 0..200 {
+                let moves = game.list_moves();
+                let m = moves[(i * 7 + 3) % moves.len()].clone();
+                game.make_move(m);
+            }
+            assert_eq!(game.verify(), Ok(()));
+        }
+    }
+}
+
+#[test]
+fn describes_moves() {
+    let game = SoccerPuzzle::new();
+    let slide = |piece, path: &[Direction]| PieceMove {
+        piece,
+        path: path.to_vec(),
+    };
+    assert_eq!(game.describe(&slide(1, &[Direction::Down])), "Large down");
+    assert_eq!(game.describe(&slide(4, &[Direction::Up])), "T2 up");
+    assert_eq!(game.describe(&slide(3, &[Direction::Left])), "Wide left");
+    assert_eq!(
+        game.describe(&slide(8, &[Direction::Right, Direction::Up])),
+        "Small 2 right then up"
+    );
+    assert_eq!(
+        format!("[{}]", game.show_move(&slide(2, &[Direction::Down]))),
+        "[T1 down]"
+    );
+    assert_eq!(
+        slide(8, &[Direction::Right, Direction::Up]).to_string(),
+        "8RU"
+    );
+}
+
+#[test]
+fn parses_move_notation() {
+    let m: PieceMove = "4dl".parse().unwrap();
+    assert_eq!(
+        m,
+        PieceMove {
+            piece: 4,
+            path: vec![Direction::Down, Direction::Left]
+        }
+    );
+    assert_eq!(
+        PieceMove::parse_list(" 4DL, 12U\n0r ").unwrap(),
+        vec![
+            m,
+            PieceMove {
+                piece: 12,
+                path: vec![Direction::Up]
+            },
+            PieceMove {
+                piece: 0,
+                path: vec![Direction::Right]
+            },
+        ]
+    );
+
+    let parse = |s: &str| s.parse::<PieceMove>().unwrap_err();
+    assert_eq!(parse(" "), ParsePieceMoveError::Empty);
+    assert_eq!(parse("D"), ParsePieceMoveError::MissingPiece { pos: 0 });
+    assert_eq!(
+        parse("12"),
+        ParsePieceMoveError::MissingDirection { pos: 0 }
+    );
+    assert_eq!(
+        parse("1UX"),
+        ParsePieceMoveError::UnknownDirection { pos: 2, found: 'X' }
+    );
+    assert_eq!(
+        parse("1U 2D"),
+        ParsePieceMoveError::UnknownDirection { pos: 2, found: ' ' }
+    );
+    assert_eq!(
+        parse("1U\r2D"),
+        ParsePieceMoveError::UnknownDirection {
+            pos: 2,
+            found: '\r'
+        }
+    );
+    assert_eq!(
+        parse(",1U,\u{a0}2D"),
+        ParsePieceMoveError::UnknownDirection { pos: 3, found: ',' }
+    );
+}
+
+#[test]
+fn replays_printed_solutions() {
+    for metric in [Metric::Step, Metric::Slide] {
+        let mut game = SoccerPuzzle::huarong_dao();
+        game.metric = metric;
+        game.solve().unwrap();
+        let notation = game.print_moves();
+
+        let mut replay = SoccerPuzzle::huarong_dao();
+        replay
+            .apply_moves(&PieceMove::parse_list(&notation).unwrap())
+            .unwrap();
+        assert!(replay.is_goal(&replay.state()));
+        assert_eq!(replay.print_moves(), notation);
+    }
+}
+
+#[test]
+fn rejects_blocked_moves() {
+    let mut game = SoccerPuzzle::new();
+    let before = game.to_string();
+    for m in ["1D", "8DU", "7RD", "10U"] {
+        let m: PieceMove = m.parse().unwrap();
+        assert_eq!(game.try_move(m.clone()), Err(BlockedMove(m)));
+    }
+    let still = PieceMove {
+        piece: 8,
+        path: vec![],
+    };
+    assert_eq!(game.try_move(still.clone()), Err(BlockedMove(still)));
+    assert_eq!(game.to_string(), before);
+    assert!(game.moves.is_empty());
+}
+
+#[test]
+fn reports_unreachable_goals() {
+    let mut game: SoccerPuzzle = "|LL|LL|\n|LL|LL|\n|SS|  |\n".parse().unwrap();
+    let before = game.to_string();
+    assert_eq!(game.solve(), Err(NoSolution));
+    assert_eq!(game.to_string(), before);
+    assert!(game.moves.is_empty());
+}
+
+#[test]
+fn replays_every_position() {
+    let mut game = SoccerPuzzle::new();
+    let start = game.to_string();
+    game.metric = Metric::Slide;
+    game.solve().unwrap();
+
+    let frames: Vec<SoccerPuzzle> = game.replay().collect();
+    assert_eq!(frames.len(), game.moves.len() + 1);
+    assert_eq!(frames[0].to_string(), start);
+    assert!(frames[0].moves.is_empty());
+    assert_eq!(frames.last().unwrap().to_string(), game.to_string());
+    for (i, frame) in frames.iter().enumerate() {
+        assert_eq!(frame.moves, game.moves[..i]);
+    }
+
+    // Undoing everything also gets back to the start
+    while game.undo().is_some() {}
+    assert_eq!(game.to_string(), start);
+}
+
+#[test]
+fn highlights_a_piece() {
+    let game = SoccerPuzzle::new();
+    let text = game.highlighted(3);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[2], "|  |\x1b[7mWW\x1b[0m|\x1b[7mWW\x1b[0m|  |");
+    assert_eq!(lines[0], game.to_string().lines().next().unwrap());
+}
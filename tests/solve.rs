@@ -0,0 +1,80 @@
+use sliding_puzzle_solver::{Board, Unsolvable};
+
+#[test]
+fn solves_known_failing_5x5() {
+    let mut board = Board::from_rows(&[
+        vec![16, 12, 7, 0, 5],
+        vec![6, 10, 4, 1, 9],
+        vec![21, 11, 24, 19, 3],
+        vec![17, 14, 2, 18, 15],
+        vec![13, 23, 22, 8, 20],
+    ])
+    .unwrap();
+    board.solve().unwrap();
+    assert!(board.check_solved());
+}
+
+#[test]
+fn solves_random_boards() {
+    for rows in 3..=8 {
+        for cols in 3..=8 {
+            for seed in 0..30 {
+                let mut board = Board::scrambled(rows, cols, seed).unwrap();
+                let start = board.to_string();
+                board.solve().unwrap();
+                assert!(
+                    board.check_solved(),
+                    "failed to solve\n{start}\nended at\n{board}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn refuses_unsolvable_boards() {
+    let board = Board::from_rows(&[vec![2, 1, 3], vec![4, 5, 6], vec![7, 8, 0]]).unwrap();
+    assert!(!board.is_solvable());
+    assert_eq!(board.clone().solve(), Err(Unsolvable));
+    assert_eq!(board.clone().solve_optimal(), Err(Unsolvable));
+}
+
+#[test]
+fn solvability_depends_on_the_width() {
+    // With an odd width only the order of the tiles matters, wherever the blank is
+    let odd = Board::from_rows(&[vec![1, 2, 3], vec![4, 5, 0], vec![6, 7, 8]]).unwrap();
+    assert!(odd.is_solvable());
+    let odd = Board::from_rows(&[vec![1, 2, 3], vec![4, 5, 0], vec![7, 6, 8]]).unwrap();
+    assert!(!odd.is_solvable());
+
+    // With an even width moving the blank up or down a row changes it too
+    let even = Board::from_rows(&[
+        vec![1, 2, 3, 4],
+        vec![5, 6, 7, 8],
+        vec![9, 10, 11, 0],
+        vec![12, 13, 14, 15],
+    ])
+    .unwrap();
+    assert!(!even.is_solvable());
+    let even = Board::from_rows(&[
+        vec![1, 2, 3, 4],
+        vec![5, 6, 7, 8],
+        vec![9, 10, 11, 0],
+        vec![13, 12, 14, 15],
+    ])
+    .unwrap();
+    assert!(even.is_solvable());
+    let even = Board::from_rows(&[vec![1, 2, 3, 4], vec![5, 6, 0, 7]]).unwrap();
+    assert!(even.is_solvable());
+    let even = Board::from_rows(&[vec![1, 2, 0, 3], vec![4, 5, 6, 7]]).unwrap();
+    assert!(!even.is_solvable());
+}
+
+#[test]
+fn solves_large_boards() {
+    for (rows, cols) in [(30, 30), (12, 40)] {
+        let mut board = Board::scrambled(rows, cols, 7).unwrap();
+        board.solve().unwrap();
+        assert!(board.check_solved(), "{}x{}", rows, cols);
+    }
+}